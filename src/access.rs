@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_access_control::AccessModeSettings;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::tasks::{default_tasks_file, due_in, Task, TaskQueue};
+use crate::util::parse_duration;
+
+#[derive(Args, Debug, Clone)]
+pub struct AccessArgs {
+    #[command(subcommand)]
+    pub action: AccessAction,
+
+    /// task file shared with the daemon, for `block --until`.
+    #[arg(long)]
+    pub tasks_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AccessAction {
+    /// Show the access control mode and the listed clients.
+    List,
+
+    /// Cut clients off the network.
+    Block {
+        /// client MAC addresses.
+        #[arg(required = true)]
+        macs: Vec<MacAddress>,
+
+        /// unblock again after e.g. 30m, 2h. Needs the daemon running.
+        #[arg(long, value_parser = parse_duration)]
+        until: Option<Duration>,
+    },
+
+    /// Give clients access again.
+    Unblock {
+        /// client MAC addresses.
+        #[arg(required = true)]
+        macs: Vec<MacAddress>,
+    },
+
+    /// Switch between blocklist and allowlist mode, or turn access control off.
+    Mode {
+        #[arg(value_parser = ["block", "allow", "off"])]
+        mode: String,
+    },
+}
+
+fn describe_mode(mode: &AccessModeSettings) -> &'static str {
+    match (mode.enable, mode.is_allowlist()) {
+        (false, _) => "off",
+        (true, false) => "blocklist, listed clients are blocked",
+        (true, true) => "allowlist, only listed clients have access",
+    }
+}
+
+/// Hostname of a connected client, to label new list entries.
+fn client_name(api: &mut MercusysHTTP, mac: MacAddress) -> String {
+    api.get_client_list()
+        .ok()
+        .and_then(|clients| {
+            clients
+                .result
+                .client_list
+                .into_iter()
+                .find(|client| mac.matches(&client.mac))
+        })
+        .map(|client| client.hostname())
+        .unwrap_or_default()
+}
+
+fn list(api: &mut MercusysHTTP) -> Result<(), String> {
+    let mode = api
+        .get_access_mode()
+        .map_err(|err| format!("failed to read access control mode: {}", err))?
+        .result;
+    let clients = api
+        .get_access_list(&mode)
+        .map_err(|err| format!("failed to read access list: {}", err))?
+        .result
+        .client_list;
+
+    println!("mode: {}", describe_mode(&mode));
+    println!("{:<17}  NAME", "MAC");
+    for client in clients {
+        println!("{:<17}  {}", client.mac, client.hostname());
+    }
+
+    Ok(())
+}
+
+fn block(
+    api: &mut MercusysHTTP,
+    queue: &TaskQueue,
+    macs: &[MacAddress],
+    until: Option<Duration>,
+    dry_run: bool,
+) -> Result<(), String> {
+    // Checked before anything changes.
+    let unblock_at = until.map(due_in).transpose()?;
+
+    for &mac in macs {
+        if dry_run {
+            eprintln!("dry run mode, would block {}", mac);
+            continue;
+        }
+
+        let name = client_name(api, mac);
+        let changed = api
+            .block_client(mac, &name)
+            .map_err(|err| format!("failed to block {}: {}", mac, err))?;
+        if changed {
+            eprintln!("{} blocked.", mac);
+        } else {
+            eprintln!("{} is already blocked.", mac);
+        }
+
+        match until.zip(unblock_at) {
+            Some((duration, due)) => {
+                queue.push(Task::Unblock { mac }, due)?;
+                eprintln!(
+                    "the daemon will unblock it in {}s (task file: {}).",
+                    duration.as_secs(),
+                    queue.path().display()
+                );
+            }
+            // Blocked for good, don't let a previous expiry undo it.
+            None => queue.cancel(&Task::Unblock { mac })?,
+        }
+    }
+
+    Ok(())
+}
+
+fn unblock(
+    api: &mut MercusysHTTP,
+    queue: &TaskQueue,
+    macs: &[MacAddress],
+    dry_run: bool,
+) -> Result<(), String> {
+    for &mac in macs {
+        if dry_run {
+            eprintln!("dry run mode, would unblock {}", mac);
+            continue;
+        }
+
+        let name = client_name(api, mac);
+        let changed = api
+            .unblock_client(mac, &name)
+            .map_err(|err| format!("failed to unblock {}: {}", mac, err))?;
+        queue.cancel(&Task::Unblock { mac })?;
+        if changed {
+            eprintln!("{} unblocked.", mac);
+        } else {
+            eprintln!("{} is not blocked.", mac);
+        }
+    }
+
+    Ok(())
+}
+
+fn set_mode(api: &mut MercusysHTTP, mode: &str, dry_run: bool) -> Result<(), String> {
+    let mut settings = api
+        .get_access_mode()
+        .map_err(|err| format!("failed to read access control mode: {}", err))?
+        .result;
+    match mode {
+        "off" => settings.enable = false,
+        mode => {
+            settings.enable = true;
+            settings.access_mode = mode.into();
+        }
+    }
+
+    if settings.enable && settings.is_allowlist() {
+        let allowed = api
+            .get_access_list(&settings)
+            .map_err(|err| format!("failed to read access list: {}", err))?
+            .result
+            .client_list;
+        if allowed.is_empty() {
+            return Err(
+                "refusing allowlist mode with an empty allowlist, it would cut off every client"
+                    .into(),
+            );
+        }
+    }
+
+    if dry_run {
+        eprintln!("dry run mode, would switch to {}", describe_mode(&settings));
+        return Ok(());
+    }
+
+    api.set_access_mode(&settings)
+        .map_err(|err| format!("failed to set access control mode: {}", err))?;
+    eprintln!("access control: {}", describe_mode(&settings));
+    Ok(())
+}
+
+pub fn run_access(api: &mut MercusysHTTP, args: &AccessArgs, dry_run: bool) -> Result<(), String> {
+    let queue = TaskQueue::new(args.tasks_file.clone().unwrap_or_else(default_tasks_file));
+
+    match &args.action {
+        AccessAction::List => list(api),
+        AccessAction::Block { macs, until } => block(api, &queue, macs, *until, dry_run),
+        AccessAction::Unblock { macs } => unblock(api, &queue, macs, dry_run),
+        AccessAction::Mode { mode } => set_mode(api, mode, dry_run),
+    }
+}
@@ -0,0 +1,230 @@
+// Router configuration backups.
+//
+// Each backup is saved as `mercusys-backup-<timestamp>.bin`, next to a
+// `.sha256` file in `sha256sum` format, so it can be verified with the usual
+// tools as well. Backups hold the admin credentials, so only the current user
+// can read them. Restoring refuses files that don't match their checksum.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use clap::{Args, Subcommand};
+use sha2::{Digest, Sha256};
+
+use crate::mercusys::http::MercusysHTTP;
+use crate::prompt::confirm;
+use crate::util::create_private_file;
+
+#[derive(Args, Debug, Clone)]
+pub struct BackupArgs {
+    #[command(subcommand)]
+    pub action: BackupAction,
+
+    /// directory holding the backups.
+    #[arg(long, default_value = ".")]
+    pub dir: PathBuf,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BackupAction {
+    /// List saved backups and verify their checksums.
+    List,
+
+    /// Download the current router configuration.
+    Create,
+
+    /// Upload a backup to the router, which then reboots.
+    Restore {
+        /// backup file, as saved by `backup create`.
+        file: PathBuf,
+
+        /// don't ask for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn checksum_path(file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".sha256");
+    path.into()
+}
+
+/// Checksum recorded next to the backup, if any.
+fn recorded_checksum(file: &Path) -> Option<String> {
+    let content = fs::read_to_string(checksum_path(file)).ok()?;
+    content
+        .split_whitespace()
+        .next()
+        .map(|hash| hash.to_lowercase())
+}
+
+fn verify(file: &Path) -> Result<Vec<u8>, String> {
+    let data =
+        fs::read(file).map_err(|err| format!("failed to read {}: {}", file.display(), err))?;
+    let expected = recorded_checksum(file)
+        .ok_or_else(|| format!("no checksum found for {}", file.display()))?;
+
+    if sha256_hex(&data) != expected {
+        return Err(format!("checksum mismatch for {}", file.display()));
+    }
+
+    Ok(data)
+}
+
+fn create(api: &mut MercusysHTTP, dir: &Path) -> Result<(), String> {
+    let data = api
+        .download_config_backup()
+        .map_err(|err| format!("failed to download backup: {}", err))?;
+    if data.is_empty() {
+        return Err("router returned an empty backup".into());
+    }
+
+    let file = save(dir, &data)?;
+    println!("{}", file.display());
+    eprintln!("saved {} bytes, sha256 {}", data.len(), sha256_hex(&data));
+    Ok(())
+}
+
+/// Create a new backup file only the current user can read, the admin
+///   credentials are in there. A backup from the same second gets a suffix.
+fn create_backup_file(dir: &Path) -> Result<(PathBuf, File), String> {
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+    for n in 1..100 {
+        let name = match n {
+            1 => format!("mercusys-backup-{}.bin", timestamp),
+            _ => format!("mercusys-backup-{}-{}.bin", timestamp, n),
+        };
+        let file = dir.join(name);
+        match create_private_file(&file) {
+            Ok(handle) => return Ok((file, handle)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(format!("failed to create {}: {}", file.display(), err)),
+        }
+    }
+    Err(format!(
+        "too many backups for {} in {}",
+        timestamp,
+        dir.display()
+    ))
+}
+
+/// Write the backup and its checksum file, returning the backup path.
+fn save(dir: &Path, data: &[u8]) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create {}: {}", dir.display(), err))?;
+
+    let (file, mut handle) = create_backup_file(dir)?;
+    let name = file.file_name().unwrap().to_string_lossy().into_owned();
+    let hash = sha256_hex(data);
+
+    handle
+        .write_all(data)
+        .and_then(|_| handle.sync_all())
+        .and_then(|_| fs::write(checksum_path(&file), format!("{}  {}\n", hash, name)))
+        .map_err(|err| format!("failed to write {}: {}", file.display(), err))?;
+
+    Ok(file)
+}
+
+/// Local only, doesn't need a router session.
+pub fn list_backups(dir: &Path) -> Result<(), String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|err| format!("failed to read {}: {}", dir.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            name.starts_with("mercusys-backup-") && name.ends_with(".bin")
+        })
+        .collect();
+    files.sort();
+
+    for file in files {
+        let size = fs::metadata(&file).map(|meta| meta.len()).unwrap_or(0);
+        let status = match verify(&file) {
+            Ok(_) => "ok".to_string(),
+            Err(err) => err,
+        };
+        println!("{}  {:>8} bytes  {}", file.display(), size, status);
+    }
+
+    Ok(())
+}
+
+fn restore(api: &mut MercusysHTTP, file: &Path, yes: bool, dry_run: bool) -> Result<(), String> {
+    let data = verify(file)?;
+
+    if dry_run {
+        eprintln!("dry run mode, {} verified, not restoring.", file.display());
+        return Ok(());
+    }
+
+    let question = format!(
+        "This replaces the router configuration with {} and reboots the mesh.",
+        file.display()
+    );
+    if !yes && !confirm(question.as_str()) {
+        return Err("restore cancelled".into());
+    }
+
+    let resp = api
+        .restore_config_backup(data)
+        .map_err(|err| format!("failed to restore backup: {}", err))?;
+    eprintln!(
+        "backup restored, router reboots with wait timeout of {}",
+        resp.result.reboot_time
+    );
+    Ok(())
+}
+
+pub fn run_backup(api: &mut MercusysHTTP, args: &BackupArgs, dry_run: bool) -> Result<(), String> {
+    match &args.action {
+        BackupAction::List => list_backups(&args.dir),
+        BackupAction::Create => create(api, &args.dir),
+        BackupAction::Restore { file, yes } => restore(api, file, *yes, dry_run),
+    }
+}
+
+#[test]
+fn save_and_verify_test() {
+    let dir = std::env::temp_dir().join(format!("mercusys-backup-test-{}", std::process::id()));
+
+    let file = save(&dir, b"config").unwrap();
+    let name = file.file_name().unwrap().to_str().unwrap().to_string();
+    assert!(name.starts_with("mercusys-backup-") && name.ends_with(".bin"));
+    assert_eq!(verify(&file), Ok(b"config".to_vec()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // Likely within the same second, must not replace the first one.
+    let again = save(&dir, b"config 2").unwrap();
+    assert_ne!(again, file);
+    assert_eq!(verify(&file), Ok(b"config".to_vec()));
+    assert_eq!(verify(&again), Ok(b"config 2".to_vec()));
+
+    // Same format as `sha256sum`.
+    let checksum = fs::read_to_string(checksum_path(&file)).unwrap();
+    assert_eq!(checksum, format!("{}  {}\n", sha256_hex(b"config"), name));
+
+    fs::write(&file, b"tampered").unwrap();
+    assert!(verify(&file).is_err());
+
+    fs::remove_file(checksum_path(&file)).unwrap();
+    assert!(verify(&file).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,97 @@
+use clap::Args;
+
+use crate::mercusys::api_client_list::SingleClientItem;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::util::format_duration;
+
+#[derive(Args, Debug, Clone)]
+pub struct ClientsArgs {
+    /// only clients attached to this node (MAC address).
+    #[arg(short, long)]
+    pub node: Option<MacAddress>,
+
+    /// only clients on this band.
+    #[arg(short, long, value_parser = ["2.4", "5", "wired"])]
+    pub band: Option<String>,
+
+    /// print the raw JSON instead of a table.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+impl ClientsArgs {
+    fn matches(&self, client: &SingleClientItem) -> bool {
+        let node_ok = match &self.node {
+            Some(node) => node.matches(&client.access_host),
+            None => true,
+        };
+        let band_ok = match &self.band {
+            Some(band) => client.band() == band,
+            None => true,
+        };
+
+        node_ok && band_ok
+    }
+}
+
+pub fn list_clients(api: &mut MercusysHTTP, args: &ClientsArgs) -> Result<(), String> {
+    let clients = api
+        .get_client_list()
+        .map_err(|err| format!("failed to read client list: {}", err))?
+        .result
+        .client_list;
+
+    let clients: Vec<&SingleClientItem> = clients
+        .iter()
+        .filter(|client| args.matches(client))
+        .collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&clients).unwrap());
+        return Ok(());
+    }
+
+    println!(
+        "{:<17}  {:<15}  {:<24}  {:<5}  {:<17}  {:>9}  {:>10}",
+        "MAC", "IP", "HOSTNAME", "BAND", "NODE", "RATE", "ONLINE"
+    );
+    for client in clients {
+        println!(
+            "{:<17}  {:<15}  {:<24}  {:<5}  {:<17}  {:>4}/{:<4}  {:>10}",
+            client.mac,
+            client.ip,
+            client.hostname(),
+            client.band(),
+            client.access_host,
+            client.tx_rate,
+            client.rx_rate,
+            format_duration(client.online_time)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn clients_filter_test() {
+    let on_node = |access_host: &str, connection_type: &str| SingleClientItem {
+        access_host: access_host.into(),
+        connection_type: connection_type.into(),
+        ..Default::default()
+    };
+    let args = |node: Option<&str>, band: Option<&str>| ClientsArgs {
+        node: node.map(|mac| mac.parse().unwrap()),
+        band: band.map(|band| band.into()),
+        json: false,
+    };
+
+    let phone = on_node("AA-BB-CC-DD-EE-01", "band5");
+
+    assert!(args(None, None).matches(&phone));
+    assert!(args(Some("aa:bb:cc:dd:ee:01"), None).matches(&phone));
+    assert!(!args(Some("aa:bb:cc:dd:ee:02"), None).matches(&phone));
+    assert!(args(None, Some("5")).matches(&phone));
+    assert!(!args(None, Some("2.4")).matches(&phone));
+    assert!(!args(Some("aa:bb:cc:dd:ee:01"), Some("wired")).matches(&phone));
+}
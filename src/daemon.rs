@@ -0,0 +1,316 @@
+// Built-in scheduler, for systems where cron is not available (e.g. minimal
+// containers).
+//
+// A single process runs every profile from the config file, sharing one
+// router session. SIGTERM/Ctrl-C stops it after logging out.
+
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Utc};
+use chrono_tz::Tz;
+use clap::Args;
+use cron::Schedule;
+use serde::Deserialize;
+
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::rolling::{rolling_reboot, RollingRebootOptions};
+use crate::tasks::{default_tasks_file, run_task, unix_now, TaskQueue};
+use crate::watchdog::{WatchArgs, Watchdog};
+
+#[derive(Args, Debug, Clone)]
+pub struct DaemonArgs {
+    /// path to the daemon config (JSON).
+    #[arg(short, long)]
+    pub config: PathBuf,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DaemonConfig {
+    /// IANA time zone, e.g. "Europe/London". Local time if omitted.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// One-off tasks queued by other commands (e.g. `guest on --for`).
+    #[serde(default)]
+    pub tasks_file: Option<PathBuf>,
+
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+/// How often the task file is checked.
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Seconds before a failed task is tried again.
+const TASK_RETRY_DELAY: u64 = 60;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+
+    /// Cron expression, either 5 fields (minute first) or 6/7 fields
+    ///   (second first). Not used by `watch` profiles.
+    #[serde(default)]
+    pub schedule: Option<String>,
+
+    /// Up to this many seconds of random delay before each run.
+    #[serde(default)]
+    pub jitter: u64,
+
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Reboot the whole mesh.
+    Reboot {
+        #[serde(default)]
+        rolling: bool,
+    },
+
+    /// Reboot only the listed nodes.
+    RebootNodes { macs: Vec<String> },
+
+    /// Run the internet watchdog.
+    Watch(WatchArgs),
+}
+
+/// Accept the classic 5 field cron syntax as well.
+pub fn parse_schedule(expr: &str) -> Result<Schedule, cron::error::Error> {
+    if expr.split_whitespace().count() == 5 {
+        Schedule::from_str(format!("0 {}", expr).as_str())
+    } else {
+        Schedule::from_str(expr)
+    }
+}
+
+fn time_until_next(schedule: &Schedule, timezone: Option<Tz>) -> Option<Duration> {
+    let next = match timezone {
+        Some(tz) => schedule.upcoming(tz).next()?.with_timezone(&Utc),
+        None => schedule.upcoming(Local).next()?.with_timezone(&Utc),
+    };
+    (next - Utc::now()).to_std().ok()
+}
+
+fn jitter(max_secs: u64) -> Duration {
+    if max_secs == 0 {
+        return Duration::ZERO;
+    }
+
+    use rand::Rng;
+    Duration::from_secs(rand::thread_rng().gen_range(0..=max_secs))
+}
+
+enum Trigger {
+    Cron(Schedule),
+    Watchdog(Watchdog),
+}
+
+struct Job {
+    profile: Profile,
+    trigger: Trigger,
+    next_run: Instant,
+}
+
+impl Job {
+    fn new(profile: Profile, dry_run: bool) -> Result<Job, String> {
+        let trigger = match (&profile.action, &profile.schedule) {
+            (Action::Watch(watch_args), _) => {
+                Trigger::Watchdog(Watchdog::new(watch_args.clone(), dry_run))
+            }
+            (_, Some(expr)) => Trigger::Cron(
+                parse_schedule(expr)
+                    .map_err(|err| format!("invalid schedule \"{}\": {}", expr, err))?,
+            ),
+            (_, None) => return Err("missing schedule".into()),
+        };
+
+        Ok(Job {
+            profile,
+            trigger,
+            next_run: Instant::now(),
+        })
+    }
+
+    fn schedule_next(&mut self, timezone: Option<Tz>) {
+        let wait = match &self.trigger {
+            Trigger::Cron(schedule) => {
+                // No upcoming time at all, e.g. a year in the past: park it.
+                time_until_next(schedule, timezone).unwrap_or(Duration::from_secs(365 * 24 * 3600))
+            }
+            Trigger::Watchdog(watchdog) => watchdog.interval(),
+        };
+
+        self.next_run = Instant::now() + wait + jitter(self.profile.jitter);
+    }
+
+    fn run(&mut self, api: &mut MercusysHTTP, dry_run: bool) {
+        let name = self.profile.name.as_str();
+
+        if let Trigger::Watchdog(watchdog) = &mut self.trigger {
+            watchdog.tick(api);
+            return;
+        }
+
+        if dry_run {
+            eprintln!("[{}] dry run mode, skip {:?}", name, self.profile.action);
+            return;
+        }
+
+        // Reboots are not retried, a failure halfway would reboot nodes twice.
+        if let Err(err) = api.ensure_session() {
+            eprintln!("[{}] reboot failed: {}", name, err);
+            return;
+        }
+
+        let result = match &self.profile.action {
+            Action::Reboot { rolling: false } => api
+                .reboot_whole_mesh()
+                .map(|resp| format!("wait timeout of {}", resp.result.reboot_time)),
+            Action::Reboot { rolling: true } => {
+                let options = RollingRebootOptions::default();
+                rolling_reboot(api, &options)
+                    .map(|report| format!("failed nodes: {:?}", report.failed))
+            }
+            Action::RebootNodes { macs } => api
+                .reboot_devices(macs)
+                .map(|resp| format!("wait timeout of {}", resp.result.reboot_time)),
+            Action::Watch(_) => unreachable!(),
+        };
+
+        match result {
+            Ok(summary) => eprintln!("[{}] reboot success, {}", name, summary),
+            Err(err) => eprintln!("[{}] reboot failed: {}", name, err),
+        }
+    }
+}
+
+fn run_due_tasks(api: &mut MercusysHTTP, queue: &TaskQueue, dry_run: bool) {
+    if dry_run {
+        // Only peek, the tasks stay queued for a real run.
+        match queue.load() {
+            Ok(tasks) => tasks
+                .iter()
+                .filter(|pending| pending.due <= unix_now())
+                .for_each(|pending| eprintln!("[tasks] dry run mode, skip {:?}", pending.task)),
+            Err(err) => eprintln!("[tasks] {}", err),
+        }
+        return;
+    }
+
+    let due = match queue.take_due(unix_now()) {
+        Ok(due) => due,
+        Err(err) => {
+            eprintln!("[tasks] {}", err);
+            return;
+        }
+    };
+
+    for pending in due {
+        match run_task(api, &pending.task) {
+            Ok(()) => eprintln!("[tasks] done: {:?}", pending.task),
+            Err(err) => {
+                // Router might be down for now, try again a bit later.
+                eprintln!("[tasks] {}, retry in {}s", err, TASK_RETRY_DELAY);
+                if let Err(err) = queue.push(pending.task, unix_now() + TASK_RETRY_DELAY) {
+                    eprintln!("[tasks] {}", err);
+                }
+            }
+        }
+    }
+}
+
+pub fn load_config(path: &PathBuf) -> Result<DaemonConfig, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    serde_json::from_str(content.as_str())
+        .map_err(|err| format!("failed to parse {}: {}", path.display(), err))
+}
+
+pub fn run_daemon(
+    api: &mut MercusysHTTP,
+    config: DaemonConfig,
+    dry_run: bool,
+) -> Result<(), String> {
+    let timezone = match &config.timezone {
+        Some(name) => Some(
+            Tz::from_str(name).map_err(|err| format!("invalid timezone \"{}\": {}", name, err))?,
+        ),
+        None => None,
+    };
+
+    let mut jobs = config
+        .profiles
+        .into_iter()
+        .map(|profile| {
+            let name = profile.name.clone();
+            Job::new(profile, dry_run).map_err(|err| format!("profile \"{}\": {}", name, err))
+        })
+        .collect::<Result<Vec<Job>, String>>()?;
+
+    for job in jobs.iter_mut() {
+        if let Trigger::Cron(_) = job.trigger {
+            job.schedule_next(timezone);
+        }
+    }
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = shutdown.clone();
+        ctrlc::set_handler(move || shutdown.store(true, Ordering::SeqCst))
+            .map_err(|err| format!("failed to install signal handler: {}", err))?;
+    }
+
+    let queue = TaskQueue::new(config.tasks_file.unwrap_or_else(default_tasks_file));
+    let mut next_task_poll = Instant::now();
+
+    eprintln!(
+        "daemon started with {} profile(s), task file: {}",
+        jobs.len(),
+        queue.path().display()
+    );
+
+    while !shutdown.load(Ordering::SeqCst) {
+        if Instant::now() >= next_task_poll {
+            run_due_tasks(api, &queue, dry_run);
+            next_task_poll = Instant::now() + TASK_POLL_INTERVAL;
+        }
+
+        // Sleep in small steps, so we can react to SIGTERM quickly.
+        let job = match jobs.iter_mut().min_by_key(|job| job.next_run) {
+            Some(job) if job.next_run <= Instant::now() => job,
+            _ => {
+                sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+
+        job.run(api, dry_run);
+        job.schedule_next(timezone);
+    }
+
+    eprintln!("shutting down...");
+    if !api.stok.is_empty() {
+        match api.logout() {
+            Ok(success) => eprintln!("logout: {}", success),
+            Err(err) => eprintln!("logout failed: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn parse_schedule_test() {
+    assert!(parse_schedule("30 4 * * *").is_ok());
+    assert!(parse_schedule("0 30 4 * * Sun").is_ok());
+    assert!(parse_schedule("30 4 * *").is_err());
+}
@@ -0,0 +1,196 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_diagnostic::{
+    DiagnosticKind, DiagnosticParam, DiagnosticStatus, PingResult, TracerouteHop,
+};
+use crate::mercusys::http::MercusysHTTP;
+
+#[derive(Args, Debug, Clone)]
+pub struct DiagArgs {
+    #[command(subcommand)]
+    pub action: DiagAction,
+
+    /// seconds to wait for the router to finish.
+    #[arg(long, default_value_t = 60)]
+    pub timeout: u64,
+
+    /// print the parsed result as JSON instead.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DiagAction {
+    /// Ping a host from the main unit.
+    Ping {
+        host: String,
+
+        #[arg(short, long, default_value_t = 4)]
+        count: u32,
+    },
+
+    /// Traceroute to a host from the main unit.
+    Traceroute {
+        host: String,
+
+        #[arg(long, default_value_t = 20)]
+        max_hops: u32,
+    },
+}
+
+/// Right after starting, the router may still report "idle", or the previous
+///   run unchanged. A run that starts and ends between two polls with the
+///   very same output would look stale too, and just times out.
+fn is_pending(status: &DiagnosticStatus, previous: &DiagnosticStatus, seen_running: bool) -> bool {
+    if seen_running {
+        return false;
+    }
+    status.status == "idle" || status == previous
+}
+
+/// Start a diagnostic on the main unit and wait for its output.
+fn run_diagnostic(
+    api: &mut MercusysHTTP,
+    kind: DiagnosticKind,
+    params: DiagnosticParam,
+    timeout: Duration,
+) -> Result<DiagnosticStatus, String> {
+    let host = params.host.clone();
+    let read_err = |err| format!("failed to read {} output: {}", kind.form(), err);
+
+    // Also refreshes an expired session, so the start below isn't retried.
+    let previous = api
+        .with_relogin(|api| api.get_diagnostic(kind))
+        .map_err(read_err)?
+        .result;
+    api.start_diagnostic(kind, params)
+        .map_err(|err| format!("failed to start {} {}: {}", kind.form(), host, err))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut seen_running = false;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(2));
+
+        let status = api
+            .with_relogin(|api| api.get_diagnostic(kind))
+            .map_err(read_err)?
+            .result;
+        if status.is_running() {
+            seen_running = true;
+            continue;
+        }
+        if is_pending(&status, &previous, seen_running) {
+            continue;
+        }
+        if !status.is_done() {
+            return Err(format!("{} {} {}", kind.form(), host, status.status));
+        }
+        return Ok(status);
+    }
+
+    Err(format!(
+        "{} {} did not finish within {}s",
+        kind.form(),
+        host,
+        timeout.as_secs()
+    ))
+}
+
+/// Ping `host` from the router itself, rather than from this machine.
+pub fn router_ping(
+    api: &mut MercusysHTTP,
+    host: &str,
+    count: u32,
+    timeout: Duration,
+) -> Result<PingResult, String> {
+    let params = DiagnosticParam {
+        host: host.into(),
+        count: Some(count),
+        max_hops: None,
+    };
+    let status = run_diagnostic(api, DiagnosticKind::Ping, params, timeout)?;
+    PingResult::parse(&status.output)
+        .ok_or_else(|| format!("ping {}: {}", host, status.output.trim()))
+}
+
+fn print_ping(host: &str, result: &PingResult) {
+    println!("host:     {}", host);
+    println!(
+        "packets:  {} sent, {} received, {:.0}% loss",
+        result.transmitted,
+        result.received,
+        result.loss_percent()
+    );
+    if let (Some(min), Some(avg), Some(max)) = (result.rtt_min, result.rtt_avg, result.rtt_max) {
+        println!(
+            "rtt:      {:.1}/{:.1}/{:.1} ms (min/avg/max)",
+            min, avg, max
+        );
+    }
+}
+
+fn print_hops(hops: &[TracerouteHop]) {
+    for hop in hops {
+        let rtt: Vec<String> = hop.rtt.iter().map(|rtt| format!("{:.1} ms", rtt)).collect();
+        println!(
+            "{:>3}  {:<40}  {}",
+            hop.hop,
+            hop.address.as_deref().unwrap_or("*"),
+            rtt.join("  ")
+        );
+    }
+}
+
+pub fn run_diag(api: &mut MercusysHTTP, args: &DiagArgs) -> Result<(), String> {
+    let timeout = Duration::from_secs(args.timeout);
+
+    match &args.action {
+        DiagAction::Ping { host, count } => {
+            eprintln!("pinging {} from the router...", host);
+            let result = router_ping(api, host, *count, timeout)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&result).unwrap());
+            } else {
+                print_ping(host, &result);
+            }
+            if !result.is_reachable() {
+                return Err(format!("{} is unreachable from the router", host));
+            }
+            Ok(())
+        }
+        DiagAction::Traceroute { host, max_hops } => {
+            eprintln!("tracing the route to {} from the router...", host);
+            let params = DiagnosticParam {
+                host: host.clone(),
+                count: None,
+                max_hops: Some(*max_hops),
+            };
+            let status = run_diagnostic(api, DiagnosticKind::Traceroute, params, timeout)?;
+            let hops = TracerouteHop::parse_all(&status.output);
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&hops).unwrap());
+            } else {
+                print_hops(&hops);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn is_pending_test() {
+    let status = |status: &str, output: &str| DiagnosticStatus {
+        status: status.into(),
+        output: output.into(),
+    };
+    let previous = status("done", "old output");
+
+    assert!(is_pending(&status("idle", ""), &previous, false));
+    assert!(is_pending(&previous.clone(), &previous, false));
+    assert!(!is_pending(&status("done", "new output"), &previous, false));
+    assert!(!is_pending(&previous.clone(), &previous, true));
+    assert!(!is_pending(&status("failed", ""), &previous, false));
+}
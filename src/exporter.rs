@@ -0,0 +1,332 @@
+// Prometheus exporter for the mesh nodes.
+//
+// Every scrape of `/metrics` reads the device list through one long lived
+// session (logging in again when it expires), and renders it in the text
+// exposition format. The result of the last speed test is exported too; with
+// `--speedtest-interval` a new test is started in the background once the
+// last one is old enough, so scrapes never wait for it. Routers whose main
+// unit reports no speed test support are not asked.
+
+use std::fmt::Write;
+use std::time::Instant;
+
+use clap::Args;
+use tiny_http::{Header, Response, Server};
+
+use crate::mercusys::api_device_list::SingleDeviceItem;
+use crate::mercusys::api_speedtest::SpeedTestResult;
+use crate::mercusys::http::MercusysHTTP;
+use crate::tasks::unix_now;
+
+#[derive(Args, Debug, Clone)]
+pub struct ExporterArgs {
+    /// address to listen on.
+    #[arg(long, default_value = "127.0.0.1:9185")]
+    pub listen: String,
+
+    /// start a speed test when the last one is older than this many seconds.
+    #[arg(long)]
+    pub speedtest_interval: Option<u64>,
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[derive(Default)]
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn gauge(&mut self, name: &str, help: &str) {
+        writeln!(self.out, "# HELP {} {}", name, help).unwrap();
+        writeln!(self.out, "# TYPE {} gauge", name).unwrap();
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+
+        if labels.is_empty() {
+            writeln!(self.out, "{} {}", name, value).unwrap();
+        } else {
+            writeln!(self.out, "{}{{{}}} {}", name, labels.join(","), value).unwrap();
+        }
+    }
+}
+
+fn bool_value(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn render_devices(metrics: &mut MetricsWriter, devices: &[SingleDeviceItem]) {
+    let online = devices
+        .iter()
+        .filter(|device| device.is_connected())
+        .count();
+
+    metrics.gauge(
+        "mercusys_nodes",
+        "Number of mesh nodes known to the router.",
+    );
+    metrics.sample("mercusys_nodes", &[], devices.len() as f64);
+
+    metrics.gauge(
+        "mercusys_nodes_online",
+        "Number of mesh nodes connected to the mesh.",
+    );
+    metrics.sample("mercusys_nodes_online", &[], online as f64);
+
+    metrics.gauge("mercusys_node_info", "Node information, value is always 1.");
+    for device in devices {
+        metrics.sample(
+            "mercusys_node_info",
+            &[
+                ("mac", &device.mac),
+                ("nickname", &device.nickname()),
+                ("role", &device.role),
+                ("model", &device.device_model),
+                ("software_ver", &device.software_ver),
+                ("hardware_ver", &device.hardware_ver),
+            ],
+            1.0,
+        );
+    }
+
+    metrics.gauge("mercusys_node_connected", "Node is connected to the mesh.");
+    for device in devices {
+        metrics.sample(
+            "mercusys_node_connected",
+            &[("mac", &device.mac)],
+            bool_value(device.is_connected()),
+        );
+    }
+
+    metrics.gauge(
+        "mercusys_node_internet_online",
+        "Node reports working internet access (inet_status).",
+    );
+    for device in devices {
+        metrics.sample(
+            "mercusys_node_internet_online",
+            &[("mac", &device.mac)],
+            bool_value(device.is_online()),
+        );
+    }
+
+    metrics.gauge(
+        "mercusys_node_signal_level",
+        "Backhaul signal level per band.",
+    );
+    for device in devices {
+        let bands = [
+            ("2.4", &device.signal_level.band2_4),
+            ("5", &device.signal_level.band5),
+        ];
+        for (band, level) in bands {
+            if let Ok(level) = level.parse::<f64>() {
+                metrics.sample(
+                    "mercusys_node_signal_level",
+                    &[("mac", &device.mac), ("band", band)],
+                    level,
+                );
+            }
+        }
+    }
+
+    metrics.gauge(
+        "mercusys_node_backhaul",
+        "Backhaul connection types in use by the node, value is always 1.",
+    );
+    for device in devices {
+        for connection_type in device.connection_type.iter().flatten() {
+            metrics.sample(
+                "mercusys_node_backhaul",
+                &[("mac", &device.mac), ("type", connection_type)],
+                1.0,
+            );
+        }
+    }
+}
+
+fn render_speed_test(metrics: &mut MetricsWriter, result: &SpeedTestResult) {
+    if result.test_time <= 0 {
+        return;
+    }
+
+    metrics.gauge(
+        "mercusys_speedtest_download_mbps",
+        "Download speed measured by the last speed test.",
+    );
+    metrics.sample(
+        "mercusys_speedtest_download_mbps",
+        &[],
+        result.download_mbps(),
+    );
+
+    metrics.gauge(
+        "mercusys_speedtest_upload_mbps",
+        "Upload speed measured by the last speed test.",
+    );
+    metrics.sample("mercusys_speedtest_upload_mbps", &[], result.upload_mbps());
+
+    metrics.gauge(
+        "mercusys_speedtest_latency_ms",
+        "Latency measured by the last speed test.",
+    );
+    metrics.sample("mercusys_speedtest_latency_ms", &[], result.latency as f64);
+
+    metrics.gauge(
+        "mercusys_speedtest_timestamp_seconds",
+        "When the last speed test completed.",
+    );
+    metrics.sample(
+        "mercusys_speedtest_timestamp_seconds",
+        &[],
+        result.test_time as f64,
+    );
+}
+
+/// Export the last speed test, and start a new one if it's getting old.
+fn scrape_speed_test(metrics: &mut MetricsWriter, api: &mut MercusysHTTP, args: &ExporterArgs) {
+    let result = match api.with_relogin(|api| api.get_speed_test()) {
+        Ok(result) => result.result,
+        Err(err) => {
+            eprintln!("failed to read speed test: {}", err);
+            return;
+        }
+    };
+    render_speed_test(metrics, &result);
+
+    if let Some(interval) = args.speedtest_interval {
+        let age = unix_now().saturating_sub(result.test_time.max(0) as u64);
+        if !result.is_running() && age >= interval {
+            if let Err(err) = api.start_speed_test() {
+                eprintln!("failed to start speed test: {}", err);
+            }
+        }
+    }
+}
+
+fn scrape(api: &mut MercusysHTTP, args: &ExporterArgs) -> String {
+    let mut metrics = MetricsWriter::default();
+
+    let start = Instant::now();
+    let devices = api.with_relogin(|api| api.get_device_list());
+    let duration = start.elapsed().as_secs_f64();
+
+    metrics.gauge("mercusys_up", "Whether the router could be scraped.");
+    metrics.sample("mercusys_up", &[], bool_value(devices.is_ok()));
+
+    metrics.gauge(
+        "mercusys_scrape_duration_seconds",
+        "Time it took to query the router.",
+    );
+    metrics.sample("mercusys_scrape_duration_seconds", &[], duration);
+
+    match devices {
+        Ok(devices) => {
+            let devices = devices.result.device_list;
+            render_devices(&mut metrics, &devices);
+
+            let main = devices.iter().find(|device| device.is_main());
+            if main.is_none_or(|main| main.speed_get_support != Some(false)) {
+                scrape_speed_test(&mut metrics, api, args);
+            }
+        }
+        Err(err) => eprintln!("scrape failed: {}", err),
+    }
+
+    metrics.out
+}
+
+pub fn run_exporter(api: &mut MercusysHTTP, args: &ExporterArgs) -> Result<(), String> {
+    let server = Server::http(args.listen.as_str())
+        .map_err(|err| format!("failed to listen on {}: {}", args.listen, err))?;
+
+    eprintln!("serving metrics on http://{}/metrics", args.listen);
+
+    for request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or_default();
+        let resp = if path == "/metrics" {
+            let content_type =
+                Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap();
+            Response::from_string(scrape(api, args)).with_header(content_type)
+        } else {
+            Response::from_string("not found").with_status_code(404)
+        };
+
+        if let Err(err) = request.respond(resp) {
+            eprintln!("failed to send response: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn render_devices_test() {
+    use crate::mercusys::api_device_list::SignalLevel;
+
+    let devices = vec![SingleDeviceItem {
+        mac: "AA-BB-CC-DD-EE-FF".into(),
+        nickname: "Garage \"Door\"".into(),
+        group_status: "connected".into(),
+        inet_status: "online".into(),
+        signal_level: SignalLevel {
+            band2_4: "3".into(),
+            band5: "".into(),
+        },
+        connection_type: Some(vec!["band5".into()]),
+        ..Default::default()
+    }];
+
+    let mut metrics = MetricsWriter::default();
+    render_devices(&mut metrics, &devices);
+
+    for expected in [
+        "mercusys_nodes_online 1\n",
+        "nickname=\"Garage \\\"Door\\\"\"",
+        "mercusys_node_connected{mac=\"AA-BB-CC-DD-EE-FF\"} 1\n",
+        "mercusys_node_internet_online{mac=\"AA-BB-CC-DD-EE-FF\"} 1\n",
+        "mercusys_node_signal_level{mac=\"AA-BB-CC-DD-EE-FF\",band=\"2.4\"} 3\n",
+        "mercusys_node_backhaul{mac=\"AA-BB-CC-DD-EE-FF\",type=\"band5\"} 1\n",
+    ] {
+        assert!(metrics.out.contains(expected), "missing {}", expected);
+    }
+    assert!(!metrics.out.contains("band=\"5\""));
+}
+
+#[test]
+fn render_speed_test_test() {
+    let mut metrics = MetricsWriter::default();
+    render_speed_test(&mut metrics, &SpeedTestResult::default());
+    assert!(metrics.out.is_empty());
+
+    let result = SpeedTestResult {
+        status: "done".into(),
+        download_speed: 250500,
+        upload_speed: 40000,
+        latency: 12,
+        test_time: 1700000000,
+    };
+    render_speed_test(&mut metrics, &result);
+    for expected in [
+        "mercusys_speedtest_download_mbps 250.5\n",
+        "mercusys_speedtest_upload_mbps 40\n",
+        "mercusys_speedtest_latency_ms 12\n",
+        "mercusys_speedtest_timestamp_seconds 1700000000\n",
+    ] {
+        assert!(metrics.out.contains(expected), "missing {}", expected);
+    }
+}
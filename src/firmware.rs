@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+
+#[cfg(test)]
+use crate::mercusys::api_device_list::make_test_device;
+use crate::mercusys::api_device_list::SingleDeviceItem;
+use crate::mercusys::api_firmware::OnlineFirmwareItem;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::mercusys::rolling::{describe, reboot_order, RollingRebootOptions};
+
+#[derive(Args, Debug, Clone)]
+pub struct FirmwareArgs {
+    #[command(subcommand)]
+    pub action: FirmwareAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum FirmwareAction {
+    /// List firmware versions, mismatches and available upgrades.
+    Status,
+
+    /// Upgrade nodes one by one, satellites first and the main unit last.
+    Upgrade(FirmwareUpgradeArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FirmwareUpgradeArgs {
+    /// only upgrade these nodes (MAC address), repeatable.
+    #[arg(long)]
+    pub mac: Vec<MacAddress>,
+
+    /// seconds to wait for each node to install and rejoin.
+    #[arg(long, default_value_t = 900)]
+    pub node_timeout: u64,
+
+    /// stop at the first node that fails to upgrade.
+    #[arg(long, default_value_t = false)]
+    pub abort_on_failure: bool,
+}
+
+/// Compare versions such as "1.1.0 Build 20230512 Rel. 51234" by their numbers.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn numbers(version: &str) -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    }
+
+    numbers(a).cmp(&numbers(b))
+}
+
+/// Nodes running older firmware than another node of the same model.
+pub fn outdated_nodes(devices: &[SingleDeviceItem]) -> Vec<(&SingleDeviceItem, &str)> {
+    let mut newest: HashMap<(&str, &str), &str> = HashMap::new();
+    for device in devices {
+        let key = (device.device_model.as_str(), device.hardware_ver.as_str());
+        let version = newest.entry(key).or_insert(device.software_ver.as_str());
+        if compare_versions(&device.software_ver, version) == Ordering::Greater {
+            *version = device.software_ver.as_str();
+        }
+    }
+
+    devices
+        .iter()
+        .filter_map(|device| {
+            let key = (device.device_model.as_str(), device.hardware_ver.as_str());
+            let newest = newest[&key];
+            (compare_versions(&device.software_ver, newest) == Ordering::Less)
+                .then_some((device, newest))
+        })
+        .collect()
+}
+
+fn print_status(devices: &[SingleDeviceItem], online: &[OnlineFirmwareItem]) {
+    println!(
+        "{:<17}  {:<10}  {:<8}  {:<36}  {:<36}",
+        "MAC", "MODEL", "HW", "FIRMWARE", "AVAILABLE"
+    );
+    for device in devices {
+        let available = online
+            .iter()
+            .find(|item| item.mac == device.mac && item.need_upgrade)
+            .map(|item| item.fw_ver.as_str())
+            .unwrap_or("-");
+
+        println!(
+            "{:<17}  {:<10}  {:<8}  {:<36}  {:<36}",
+            device.mac, device.device_model, device.hardware_ver, device.software_ver, available
+        );
+        if device.oversized_firmware {
+            println!("  note: {} reports an oversized firmware", device.mac);
+        }
+    }
+
+    let outdated = outdated_nodes(devices);
+    if outdated.is_empty() {
+        println!("\nall nodes of the same model run the same firmware.");
+    } else {
+        println!("\nmismatched firmware:");
+        for (device, newest) in outdated {
+            println!(
+                "  {} runs {}, other {} nodes run {}",
+                describe(device),
+                device.software_ver,
+                device.device_model,
+                newest
+            );
+        }
+    }
+}
+
+/// Wait for the node to install the firmware and come back running it. Only
+///   a node seen upgrading that rejoined with a newer version counts, errors
+///   and timeouts are failures.
+fn wait_for_upgrade(
+    api: &mut MercusysHTTP,
+    device: &SingleDeviceItem,
+    options: &RollingRebootOptions,
+) -> bool {
+    let deadline = Instant::now() + options.node_timeout;
+    let mut seen_upgrading = false;
+    let mut last_progress = -1;
+
+    while Instant::now() < deadline {
+        sleep(options.poll_interval);
+
+        if seen_upgrading {
+            // Session is gone if the main unit rebooted.
+            if let Ok(devices) = api.with_relogin(|api| api.get_device_list()) {
+                let upgraded = devices.result.device_list.iter().any(|node| {
+                    node.mac == device.mac
+                        && node.is_connected()
+                        && compare_versions(&node.software_ver, &device.software_ver)
+                            == Ordering::Greater
+                });
+                if upgraded {
+                    return true;
+                }
+            }
+        }
+
+        let status = match api.get_firmware_upgrade_status() {
+            Ok(status) => status.result.device_list,
+            // Expected while the node reboots to apply the firmware.
+            Err(_) if seen_upgrading => continue,
+            Err(err) => {
+                eprintln!("  failed to poll upgrade status: {}", err);
+                continue;
+            }
+        };
+
+        // Missing, "idle" or a "done" left from an earlier upgrade: either not
+        //   started yet, or already rebooting.
+        let status = match status.into_iter().find(|item| item.mac == device.mac) {
+            Some(status) => status,
+            None => continue,
+        };
+
+        if status.is_failed() {
+            return false;
+        }
+        if status.is_in_progress() {
+            seen_upgrading = true;
+            if status.progress != last_progress {
+                eprintln!("  {} {}%", status.upgrade_status, status.progress);
+                last_progress = status.progress;
+            }
+        }
+    }
+
+    false
+}
+
+fn upgrade(
+    api: &mut MercusysHTTP,
+    args: &FirmwareUpgradeArgs,
+    devices: &[SingleDeviceItem],
+    online: &[OnlineFirmwareItem],
+    dry_run: bool,
+) -> Result<(), String> {
+    let options = RollingRebootOptions {
+        node_timeout: Duration::from_secs(args.node_timeout),
+        abort_on_failure: args.abort_on_failure,
+        ..Default::default()
+    };
+
+    let wanted = |device: &SingleDeviceItem| {
+        let selected = args.mac.is_empty() || args.mac.iter().any(|mac| mac.matches(&device.mac));
+        let available = online
+            .iter()
+            .any(|item| item.mac == device.mac && item.need_upgrade);
+        selected && available
+    };
+
+    let targets: Vec<&SingleDeviceItem> = reboot_order(devices)
+        .into_iter()
+        .filter(|device| wanted(device))
+        .collect();
+    if targets.is_empty() {
+        eprintln!("nothing to upgrade.");
+        return Ok(());
+    }
+
+    let mut failed = vec![];
+    for device in targets {
+        if dry_run {
+            eprintln!("dry run mode, would upgrade {}", describe(device));
+            continue;
+        }
+
+        eprintln!("upgrading {}...", describe(device));
+        if let Err(err) = api.upgrade_firmware(&[device.mac.as_str()]) {
+            eprintln!("  failed to start upgrade: {}", err);
+            failed.push(device.mac.clone());
+        } else if wait_for_upgrade(api, device, &options) {
+            eprintln!("  {} upgraded and rejoined the mesh", device.mac);
+        } else {
+            eprintln!("  upgrade failed, or the node did not rejoin with the new firmware in time");
+            failed.push(device.mac.clone());
+        }
+
+        if !failed.is_empty() && options.abort_on_failure {
+            break;
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(format!(
+            "firmware upgrade failed for: {}",
+            failed.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn run_firmware(
+    api: &mut MercusysHTTP,
+    args: &FirmwareArgs,
+    dry_run: bool,
+) -> Result<(), String> {
+    let devices = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list;
+    let online = api
+        .check_online_firmware()
+        .map_err(|err| format!("failed to check online firmware: {}", err))?
+        .result
+        .device_list;
+
+    match &args.action {
+        FirmwareAction::Status => {
+            print_status(&devices, &online);
+            Ok(())
+        }
+        FirmwareAction::Upgrade(upgrade_args) => {
+            upgrade(api, upgrade_args, &devices, &online, dry_run)
+        }
+    }
+}
+
+#[test]
+fn outdated_nodes_test() {
+    let node = |mac: &str, model: &str, version: &str| SingleDeviceItem {
+        device_model: model.into(),
+        hardware_ver: "1.0".into(),
+        software_ver: version.into(),
+        ..make_test_device(mac, "slave")
+    };
+
+    let devices = vec![
+        node("a", "H80X", "1.1.0 Build 20230512 Rel. 51234"),
+        node("b", "H80X", "1.0.9 Build 20221101 Rel. 40000"),
+        node("c", "H80X", "1.1.0 Build 20230512 Rel. 51234"),
+        node("d", "H30G", "1.0.0 Build 20220101 Rel. 10000"),
+    ];
+
+    let outdated: Vec<&str> = outdated_nodes(&devices)
+        .iter()
+        .map(|(device, _)| device.mac.as_str())
+        .collect();
+    assert_eq!(outdated, vec!["b"]);
+
+    assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+}
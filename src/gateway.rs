@@ -0,0 +1,186 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+
+#[cfg(test)]
+use crate::mercusys::api_device_list::make_test_device;
+use crate::mercusys::api_device_list::SingleDeviceItem;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::mercusys::rolling::describe;
+
+#[derive(Args, Debug, Clone)]
+pub struct SetGatewayArgs {
+    /// node to become the main unit. It needs the cable to the modem.
+    #[arg(long)]
+    pub mac: MacAddress,
+
+    /// seconds to wait for the mesh to reconverge.
+    #[arg(long, default_value_t = 300)]
+    pub timeout: u64,
+}
+
+/// `main` is the only main unit, and every node in `expected` is back.
+fn is_converged(devices: &[SingleDeviceItem], main: MacAddress, expected: &[String]) -> bool {
+    let mains: Vec<&SingleDeviceItem> = devices.iter().filter(|device| device.is_main()).collect();
+    let main_ok = mains.len() == 1 && main.matches(&mains[0].mac);
+
+    let nodes_ok = expected.iter().all(|mac| {
+        devices
+            .iter()
+            .any(|device| &device.mac == mac && device.is_connected())
+    });
+
+    main_ok && nodes_ok
+}
+
+/// The node to promote, checked before anything else (dry run included).
+fn find_promotable(
+    devices: &[SingleDeviceItem],
+    mac: MacAddress,
+) -> Result<&SingleDeviceItem, String> {
+    let device = devices
+        .iter()
+        .find(|device| mac.matches(&device.mac))
+        .ok_or_else(|| format!("no node with MAC {} in the mesh", mac))?;
+    if device.is_main() {
+        return Err(format!("{} already is the main unit", describe(device)));
+    }
+    if !device.set_gateway_support {
+        return Err(format!("{} can't be the main unit", describe(device)));
+    }
+    if !device.is_connected() {
+        return Err(format!("{} is not connected to the mesh", describe(device)));
+    }
+    Ok(device)
+}
+
+fn print_roles(devices: &[SingleDeviceItem]) {
+    println!("{:<17}  {:<8}  {:<24}  STATUS", "MAC", "ROLE", "NICKNAME");
+    for device in devices {
+        println!(
+            "{:<17}  {:<8}  {:<24}  {}",
+            device.mac,
+            device.role,
+            device.nickname(),
+            device.group_status
+        );
+    }
+}
+
+pub fn run_set_gateway(
+    api: &mut MercusysHTTP,
+    args: &SetGatewayArgs,
+    dry_run: bool,
+) -> Result<(), String> {
+    let devices = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list;
+    let expected: Vec<String> = devices
+        .iter()
+        .filter(|device| device.is_connected())
+        .map(|device| device.mac.clone())
+        .collect();
+
+    let target = find_promotable(&devices, args.mac)?;
+
+    if dry_run {
+        eprintln!(
+            "dry run mode, would make {} the main unit",
+            describe(target)
+        );
+        return Ok(());
+    }
+
+    let resp = api
+        .set_gateway(args.mac)
+        .map_err(|err| format!("failed to set gateway: {}", err))?;
+    eprintln!(
+        "switching main unit to {}, router expects {}s...",
+        args.mac, resp.result.wait_time
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(args.timeout);
+    let mut last_devices = devices;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(5));
+
+        // The old main unit drops our session along the way.
+        match api.with_relogin(|api| api.get_device_list()) {
+            Ok(devices) => last_devices = devices.result.device_list,
+            Err(err) => {
+                eprintln!("  waiting for the mesh: {}", err);
+                continue;
+            }
+        }
+
+        if is_converged(&last_devices, args.mac, &expected) {
+            eprintln!("mesh reconverged.");
+            print_roles(&last_devices);
+            return Ok(());
+        }
+    }
+
+    print_roles(&last_devices);
+    Err(format!("mesh did not reconverge within {}s", args.timeout))
+}
+
+#[test]
+fn is_converged_test() {
+    let main: MacAddress = "AA-AA-AA-AA-AA-02".parse().unwrap();
+    let expected = vec![
+        "AA-AA-AA-AA-AA-01".to_string(),
+        "AA-AA-AA-AA-AA-02".to_string(),
+    ];
+
+    let devices = vec![
+        make_test_device("AA-AA-AA-AA-AA-01", "master"),
+        make_test_device("AA-AA-AA-AA-AA-02", "slave"),
+    ];
+    assert!(!is_converged(&devices, main, &expected));
+
+    let devices = vec![
+        SingleDeviceItem {
+            group_status: "disconnected".into(),
+            ..make_test_device("AA-AA-AA-AA-AA-01", "slave")
+        },
+        make_test_device("AA-AA-AA-AA-AA-02", "master"),
+    ];
+    assert!(!is_converged(&devices, main, &expected));
+
+    let devices = vec![
+        make_test_device("AA-AA-AA-AA-AA-01", "slave"),
+        make_test_device("AA-AA-AA-AA-AA-02", "master"),
+    ];
+    assert!(is_converged(&devices, main, &expected));
+}
+
+#[test]
+fn find_promotable_test() {
+    let mac = |mac: &str| mac.parse::<MacAddress>().unwrap();
+    let devices = vec![
+        make_test_device("AA-AA-AA-AA-AA-01", "master"),
+        SingleDeviceItem {
+            set_gateway_support: true,
+            ..make_test_device("AA-AA-AA-AA-AA-02", "slave")
+        },
+        make_test_device("AA-AA-AA-AA-AA-03", "slave"),
+        SingleDeviceItem {
+            set_gateway_support: true,
+            group_status: "disconnected".into(),
+            ..make_test_device("AA-AA-AA-AA-AA-04", "slave")
+        },
+    ];
+
+    let target = find_promotable(&devices, mac("aa:aa:aa:aa:aa:02")).unwrap();
+    assert_eq!(target.mac, "AA-AA-AA-AA-AA-02");
+
+    // Already main, not supported, disconnected, not in the mesh.
+    assert!(find_promotable(&devices, mac("AA-AA-AA-AA-AA-01")).is_err());
+    assert!(find_promotable(&devices, mac("AA-AA-AA-AA-AA-03")).is_err());
+    assert!(find_promotable(&devices, mac("AA-AA-AA-AA-AA-04")).is_err());
+    assert!(find_promotable(&devices, mac("AA-AA-AA-AA-AA-05")).is_err());
+}
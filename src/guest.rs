@@ -0,0 +1,245 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::builder::BoolishValueParser;
+use clap::{Args, Subcommand};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+use crate::mercusys::api_guest::GuestSettings;
+use crate::mercusys::http::MercusysHTTP;
+use crate::tasks::{default_tasks_file, due_in, Task, TaskQueue};
+use crate::util::parse_duration;
+
+#[derive(Args, Debug, Clone)]
+pub struct GuestArgs {
+    #[command(subcommand)]
+    pub action: GuestAction,
+
+    /// task file shared with the daemon, for `on --for`.
+    #[arg(long)]
+    pub tasks_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum GuestAction {
+    /// Show the guest network settings.
+    Show {
+        /// print the passphrase as well.
+        #[arg(long, default_value_t = false)]
+        show_password: bool,
+
+        /// print a Wi-Fi join QR code.
+        #[arg(long, default_value_t = false)]
+        qr: bool,
+    },
+
+    /// Turn the guest network on, optionally only for a while.
+    On(GuestOnArgs),
+
+    /// Turn the guest network off.
+    Off,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct GuestOnArgs {
+    /// turn it off again after e.g. 4h, 90m. Needs the daemon running.
+    #[arg(long = "for", value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+
+    /// guest network name.
+    #[arg(long)]
+    pub ssid: Option<String>,
+
+    /// guest passphrase.
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// security mode.
+    #[arg(long, value_parser = ["none", "psk", "psk_sae", "sae"])]
+    pub security: Option<String>,
+
+    /// upload limit in Kbps, 0 for unlimited.
+    #[arg(long)]
+    pub upload_limit: Option<i64>,
+
+    /// download limit in Kbps, 0 for unlimited.
+    #[arg(long)]
+    pub download_limit: Option<i64>,
+
+    /// keep guests away from each other and the main network (on/off).
+    #[arg(long, value_parser = BoolishValueParser::new())]
+    pub isolation: Option<bool>,
+
+    /// don't print the Wi-Fi join QR code.
+    #[arg(long, default_value_t = false)]
+    pub no_qr: bool,
+}
+
+impl GuestOnArgs {
+    fn apply(&self, settings: &mut GuestSettings) {
+        settings.network.enable = true;
+        if let Some(ssid) = &self.ssid {
+            settings.network.set_ssid(ssid);
+        }
+        if let Some(password) = &self.password {
+            settings.network.set_password(password);
+        }
+        if let Some(security) = &self.security {
+            settings.network.encryption = security.clone();
+        }
+        if let Some(limit) = self.upload_limit {
+            settings.upload_limit = limit;
+        }
+        if let Some(limit) = self.download_limit {
+            settings.download_limit = limit;
+        }
+        if let Some(isolation) = self.isolation {
+            settings.isolation = isolation;
+        }
+    }
+}
+
+fn escape_qr_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The `WIFI:` payload understood by phone cameras.
+pub fn wifi_qr_payload(settings: &GuestSettings) -> String {
+    let network = &settings.network;
+    let ssid = escape_qr_field(network.ssid().as_str());
+
+    let mut payload = if network.encryption == "none" {
+        format!("WIFI:T:nopass;S:{};", ssid)
+    } else {
+        let password = escape_qr_field(network.password().as_str());
+        format!("WIFI:T:WPA;S:{};P:{};", ssid, password)
+    };
+    if network.hide_ssid {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    payload
+}
+
+fn print_qr(settings: &GuestSettings) {
+    match QrCode::new(wifi_qr_payload(settings).as_bytes()) {
+        Ok(code) => {
+            // Inverted, as most terminals have a dark background.
+            let image = code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build();
+            println!("{}", image);
+        }
+        Err(err) => eprintln!("failed to generate QR code: {}", err),
+    }
+}
+
+fn print_settings(settings: &GuestSettings, show_password: bool) {
+    let network = &settings.network;
+    let password = if show_password {
+        network.password()
+    } else {
+        "********".into()
+    };
+
+    println!("enabled:   {}", network.enable);
+    println!("ssid:      {}", network.ssid());
+    println!("security:  {}", network.encryption);
+    println!("password:  {}", password);
+    println!("isolation: {}", settings.isolation);
+    println!("upload:    {} Kbps", settings.upload_limit);
+    println!("download:  {} Kbps", settings.download_limit);
+}
+
+pub fn run_guest(api: &mut MercusysHTTP, args: &GuestArgs, dry_run: bool) -> Result<(), String> {
+    let queue = TaskQueue::new(args.tasks_file.clone().unwrap_or_else(default_tasks_file));
+
+    let mut settings = api
+        .get_guest_settings()
+        .map_err(|err| format!("failed to read guest settings: {}", err))?
+        .result;
+
+    match &args.action {
+        GuestAction::Show { show_password, qr } => {
+            print_settings(&settings, *show_password);
+            if *qr {
+                print_qr(&settings);
+            }
+        }
+        GuestAction::On(on_args) => {
+            on_args.apply(&mut settings);
+            settings.validate()?;
+            // Checked before anything changes.
+            let off_at = on_args.duration.map(due_in).transpose()?;
+
+            if dry_run {
+                eprintln!("dry run mode, would write:");
+                print_settings(&settings, false);
+                return Ok(());
+            }
+
+            api.set_guest_settings(&settings)
+                .map_err(|err| format!("failed to enable guest network: {}", err))?;
+            eprintln!("guest network enabled.");
+
+            match on_args.duration.zip(off_at) {
+                Some((duration, due)) => {
+                    queue.push(Task::GuestOff, due)?;
+                    eprintln!(
+                        "the daemon will turn it off in {}s (task file: {}).",
+                        duration.as_secs(),
+                        queue.path().display()
+                    );
+                }
+                // Someone turned it on for good, don't switch it off under them.
+                None => queue.cancel(&Task::GuestOff)?,
+            }
+
+            if !on_args.no_qr {
+                print_qr(&settings);
+            }
+        }
+        GuestAction::Off => {
+            if dry_run {
+                eprintln!("dry run mode, would disable the guest network.");
+                return Ok(());
+            }
+
+            api.set_guest_enabled(false)
+                .map_err(|err| format!("failed to disable guest network: {}", err))?;
+            queue.cancel(&Task::GuestOff)?;
+            eprintln!("guest network disabled.");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn wifi_qr_payload_test() {
+    let mut settings = GuestSettings::default();
+    settings.network.set_ssid("Guest;Net");
+    settings.network.set_password("pass:word");
+    settings.network.encryption = "psk".into();
+    assert_eq!(
+        wifi_qr_payload(&settings),
+        "WIFI:T:WPA;S:Guest\\;Net;P:pass\\:word;;"
+    );
+
+    settings.network.encryption = "none".into();
+    settings.network.hide_ssid = true;
+    assert_eq!(
+        wifi_qr_payload(&settings),
+        "WIFI:T:nopass;S:Guest\\;Net;H:true;;"
+    );
+}
@@ -0,0 +1,73 @@
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_led::LedSettings;
+use crate::mercusys::http::MercusysHTTP;
+use crate::util::parse_time_range;
+
+#[derive(Args, Debug, Clone)]
+pub struct LedArgs {
+    #[command(subcommand)]
+    pub action: LedAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum LedAction {
+    /// Show the LED setting and night mode.
+    Status,
+
+    /// Turn the status lights on, night mode still applies.
+    On,
+
+    /// Turn the status lights off.
+    Off,
+
+    /// Turn the lights off every night, e.g. 22:00-07:00, or "off".
+    Schedule { range: String },
+}
+
+fn print_settings(settings: &LedSettings) {
+    println!("led:        {}", if settings.enable { "on" } else { "off" });
+    if settings.night_mode.enable {
+        println!(
+            "night mode: {}-{}",
+            settings.night_mode.start_time, settings.night_mode.end_time
+        );
+    } else {
+        println!("night mode: off");
+    }
+}
+
+pub fn run_led(api: &mut MercusysHTTP, args: &LedArgs, dry_run: bool) -> Result<(), String> {
+    let mut settings = api
+        .get_led_settings()
+        .map_err(|err| format!("failed to read LED settings: {}", err))?
+        .result;
+
+    match &args.action {
+        LedAction::Status => {
+            print_settings(&settings);
+            return Ok(());
+        }
+        LedAction::On => settings.enable = true,
+        LedAction::Off => settings.enable = false,
+        LedAction::Schedule { range } if range == "off" => settings.night_mode.enable = false,
+        LedAction::Schedule { range } => {
+            let (start, end) = parse_time_range(range)?;
+            settings.night_mode.enable = true;
+            settings.night_mode.start_time = start;
+            settings.night_mode.end_time = end;
+        }
+    }
+    settings.validate()?;
+
+    if dry_run {
+        eprintln!("dry run mode, would write:");
+        print_settings(&settings);
+        return Ok(());
+    }
+
+    api.set_led_settings(&settings)
+        .map_err(|err| format!("failed to write LED settings: {}", err))?;
+    print_settings(&settings);
+    Ok(())
+}
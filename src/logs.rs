@@ -0,0 +1,299 @@
+// Router system log: print it, follow it, and forward it to a syslog receiver.
+//
+// The router only hands out its whole (small, rotating) log buffer, so
+// `--follow` keeps the previous snapshot around, finds where it overlaps the
+// new one, and only emits what comes after that.
+
+use std::fmt;
+use std::io::Write;
+use std::net::{TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use chrono::{Local, SecondsFormat, TimeZone};
+use clap::Args;
+
+use crate::mercusys::api_log::{LogEntry, LogLevel};
+use crate::mercusys::http::MercusysHTTP;
+
+/// Syslog facility used for forwarded entries (local0).
+const SYSLOG_FACILITY: u8 = 16;
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsArgs {
+    /// keep polling and print new entries as they show up.
+    #[arg(short, long, default_value_t = false)]
+    pub follow: bool,
+
+    /// seconds between polls with --follow.
+    #[arg(long, default_value_t = 10)]
+    pub interval: u64,
+
+    /// only print the last N entries of the current log, 0 to only follow new ones.
+    #[arg(short = 'n', long)]
+    pub lines: Option<usize>,
+
+    /// least severe level to show, e.g. warning.
+    #[arg(long, default_value = "debug")]
+    pub level: LogLevel,
+
+    /// also forward entries as RFC 5424 syslog, e.g. udp://127.0.0.1:514 or tcp://127.0.0.1:601.
+    #[arg(long)]
+    pub syslog: Option<SyslogTarget>,
+
+    /// clear the router log instead of printing it.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["follow", "syslog"])]
+    pub clear: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyslogTarget {
+    Udp(String),
+    Tcp(String),
+}
+
+impl FromStr for SyslogTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once("://") {
+            Some(("udp", addr)) if addr.contains(':') => Ok(SyslogTarget::Udp(addr.into())),
+            Some(("tcp", addr)) if addr.contains(':') => Ok(SyslogTarget::Tcp(addr.into())),
+            _ => Err(format!(
+                "invalid syslog target \"{}\", expected udp://HOST:PORT or tcp://HOST:PORT",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SyslogTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyslogTarget::Udp(addr) => write!(f, "udp://{}", addr),
+            SyslogTarget::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+/// Keep only characters allowed in syslog header fields.
+fn header_field(value: &str, max_len: usize) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if value.is_empty() {
+        "-".into()
+    } else {
+        value
+    }
+}
+
+/// Format an entry as an RFC 5424 message.
+pub fn format_rfc5424(entry: &LogEntry, hostname: &str) -> String {
+    let priority = SYSLOG_FACILITY * 8 + entry.level.severity();
+
+    // The router doesn't say which timezone it logs in, assume ours.
+    let timestamp = entry
+        .time
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .unwrap_or_else(Local::now)
+        .to_rfc3339_opts(SecondsFormat::Secs, false);
+
+    let app_name = if entry.module.is_empty() {
+        "mercusys"
+    } else {
+        entry.module.as_str()
+    };
+
+    format!(
+        "<{}>1 {} {} {} - - - {}",
+        priority,
+        timestamp,
+        header_field(hostname, 255),
+        header_field(app_name, 48),
+        entry.message
+    )
+}
+
+enum SyslogConnection {
+    Udp(UdpSocket),
+    Tcp(Option<TcpStream>),
+}
+
+pub struct SyslogForwarder {
+    target: SyslogTarget,
+    hostname: String,
+    connection: SyslogConnection,
+}
+
+impl SyslogForwarder {
+    pub fn new(target: SyslogTarget, hostname: &str) -> Result<SyslogForwarder, String> {
+        let connection = match &target {
+            SyslogTarget::Udp(addr) => {
+                let socket = UdpSocket::bind("0.0.0.0:0")
+                    .and_then(|socket| socket.connect(addr).map(|_| socket))
+                    .map_err(|err| format!("failed to open {}: {}", target, err))?;
+                SyslogConnection::Udp(socket)
+            }
+            // Connected lazily, the receiver may come and go.
+            SyslogTarget::Tcp(_) => SyslogConnection::Tcp(None),
+        };
+
+        Ok(SyslogForwarder {
+            target,
+            hostname: hostname.into(),
+            connection,
+        })
+    }
+
+    pub fn send(&mut self, entry: &LogEntry) -> Result<(), String> {
+        let message = format_rfc5424(entry, &self.hostname);
+        let err = |err: std::io::Error| format!("failed to send to {}: {}", self.target, err);
+
+        match (&mut self.connection, &self.target) {
+            (SyslogConnection::Udp(socket), _) => {
+                socket.send(message.as_bytes()).map(|_| ()).map_err(err)
+            }
+            (SyslogConnection::Tcp(stream), SyslogTarget::Tcp(addr)) => {
+                if stream.is_none() {
+                    *stream = Some(TcpStream::connect(addr.as_str()).map_err(err)?);
+                }
+
+                // Octet counting framing, RFC 6587.
+                let frame = format!("{} {}", message.len(), message);
+                let result = stream.as_mut().unwrap().write_all(frame.as_bytes());
+                if result.is_err() {
+                    *stream = None;
+                }
+                result.map_err(err)
+            }
+            (SyslogConnection::Tcp(_), SyslogTarget::Udp(_)) => unreachable!(),
+        }
+    }
+}
+
+/// Remembers the last snapshot of the log to tell new lines apart.
+#[derive(Default)]
+pub struct LogFollower {
+    previous: Vec<String>,
+}
+
+impl LogFollower {
+    /// Entries appended since the previous snapshot. Old entries rotate out at
+    ///   the front, so the longest tail of the previous snapshot that starts
+    ///   the new one marks where we left off. Repeated lines are kept.
+    pub fn new_entries(&mut self, entries: Vec<LogEntry>) -> Vec<LogEntry> {
+        let raw: Vec<String> = entries.iter().map(|entry| entry.raw.clone()).collect();
+
+        // No overlap at all (e.g. the log was cleared): everything is new.
+        let overlap = (0..self.previous.len())
+            .map(|start| &self.previous[start..])
+            .find(|tail| raw.starts_with(tail))
+            .map(|tail| tail.len())
+            .unwrap_or(0);
+
+        self.previous = raw;
+        entries.into_iter().skip(overlap).collect()
+    }
+}
+
+fn emit(entries: &[LogEntry], forwarder: &mut Option<SyslogForwarder>) {
+    for entry in entries {
+        println!("{}", entry);
+        if let Some(forwarder) = forwarder {
+            if let Err(err) = forwarder.send(entry) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+}
+
+pub fn run_logs(api: &mut MercusysHTTP, args: &LogsArgs, dry_run: bool) -> Result<(), String> {
+    if args.clear {
+        if dry_run {
+            eprintln!("dry run mode, would clear the router log.");
+            return Ok(());
+        }
+
+        api.clear_system_log()
+            .map_err(|err| format!("failed to clear log: {}", err))?;
+        eprintln!("router log cleared.");
+        return Ok(());
+    }
+
+    let mut forwarder = match &args.syslog {
+        Some(target) => {
+            let hostname = api.base_url().host_str().unwrap_or("mercusys").to_string();
+            Some(SyslogForwarder::new(target.clone(), &hostname)?)
+        }
+        None => None,
+    };
+
+    let mut follower = LogFollower::default();
+    let mut first = true;
+    loop {
+        let read = if args.follow {
+            api.with_relogin(|api| api.get_system_log())
+        } else {
+            api.get_system_log()
+        };
+
+        match read {
+            Ok(log) => {
+                let mut entries = follower.new_entries(log.result.entries());
+                entries.retain(|entry| entry.level <= args.level);
+                if first {
+                    if let Some(lines) = args.lines {
+                        entries.drain(..entries.len().saturating_sub(lines));
+                    }
+                    first = false;
+                }
+                emit(&entries, &mut forwarder);
+            }
+            Err(err) if args.follow => eprintln!("failed to read log: {}", err),
+            Err(err) => return Err(format!("failed to read log: {}", err)),
+        }
+
+        if !args.follow {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(args.interval));
+    }
+}
+
+#[test]
+fn log_follower_test() {
+    let entries = |lines: &[&str]| lines.iter().map(|line| LogEntry::parse(line)).collect();
+
+    let mut follower = LogFollower::default();
+    assert_eq!(follower.new_entries(entries(&["a", "b"])).len(), 2);
+
+    let mut new = |lines: &[&str]| -> Vec<String> {
+        let new = follower.new_entries(entries(lines));
+        new.into_iter().map(|entry| entry.raw).collect()
+    };
+
+    // "a" rotated out.
+    assert_eq!(new(&["b", "c", "d"]), vec!["c", "d"]);
+    // A line repeating an earlier one is still new.
+    assert_eq!(new(&["b", "c", "d", "c"]), vec!["c"]);
+    assert!(new(&["b", "c", "d", "c"]).is_empty());
+    // Cleared log.
+    assert_eq!(new(&["e"]), vec!["e"]);
+}
+
+#[test]
+fn format_rfc5424_test() {
+    let entry = LogEntry::parse("2024-01-06 12:34:56 DHCPD WARNING lease table full");
+    let message = format_rfc5424(&entry, "192.168.68.1");
+
+    // local0.warning
+    assert!(message.starts_with("<132>1 2024-01-06T12:34:56"));
+    assert!(message.ends_with(" 192.168.68.1 DHCPD - - - lease table full"));
+
+    assert!("udp://127.0.0.1:514".parse::<SyslogTarget>().is_ok());
+    assert!("127.0.0.1:514".parse::<SyslogTarget>().is_err());
+}
@@ -1,8 +1,65 @@
+mod access;
+mod backup;
+mod clients;
+mod daemon;
+mod diag;
+mod exporter;
+mod firmware;
+mod gateway;
+mod guest;
+mod led;
+mod logs;
 mod mercusys;
+mod mode;
+mod node;
+mod passwd;
+mod ports;
+mod profile;
+mod prompt;
+mod rename;
+mod reservations;
+mod schedule;
+mod server;
+mod speedtest;
+mod tasks;
+mod util;
+mod wan;
+mod watchdog;
+mod wifi;
 
-use clap::Parser;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use access::{run_access, AccessArgs};
+use backup::{list_backups, run_backup, BackupAction, BackupArgs};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use clients::{list_clients, ClientsArgs};
+use daemon::{load_config, run_daemon, DaemonArgs};
+use diag::{run_diag, DiagArgs};
+use exporter::{run_exporter, ExporterArgs};
+use firmware::{run_firmware, FirmwareArgs};
+use gateway::{run_set_gateway, SetGatewayArgs};
+use guest::{run_guest, GuestArgs};
+use led::{run_led, LedArgs};
+use logs::{run_logs, LogsArgs};
 use mercusys::http::MercusysHTTP;
+use mercusys::rolling::{reboot_order, rolling_reboot, RollingRebootOptions};
+use mode::{run_mode, ModeArgs};
+use node::{run_node, NodeArgs};
+use passwd::{read_password_file, run_passwd, PasswdArgs};
+use ports::{run_ports, PortsArgs};
+use profile::{run_profile, ProfileArgs};
+use rename::{run_rename, RenameArgs};
 use reqwest::Url;
+use reservations::{run_reservations, ReservationsArgs};
+use schedule::{run_schedule, ScheduleArgs};
+use server::{serve, ServeArgs};
+use speedtest::{run_speedtest, SpeedtestArgs};
+use wan::{run_wan, WanArgs};
+use watchdog::{WatchArgs, Watchdog};
+use wifi::{run_wifi, WifiArgs};
 
 /// Mercusys Halo WiFi Mesh Reboot Tool
 #[derive(Parser, Debug)]
@@ -18,7 +75,12 @@ struct Args {
 
     /// Router admin password
     #[arg(short, long)]
-    password: String,
+    password: Option<String>,
+
+    /// read the router admin password from this file (first line) instead.
+    ///   Updated by `passwd`.
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
 
     /// verbose logging (request body/decrypted response)
     #[arg(short, long, default_value_t = false)]
@@ -27,26 +89,271 @@ struct Args {
     /// don't actually reboot.
     #[arg(short = 'd', long, default_value_t = false)]
     dry_run: bool,
+
+    /// reboot one node at a time, satellites first and the main unit last.
+    #[arg(short = 'r', long, default_value_t = false)]
+    rolling: bool,
+
+    /// rolling reboot: seconds to wait for each satellite to rejoin.
+    #[arg(long, default_value_t = 300)]
+    node_timeout: u64,
+
+    /// rolling reboot: stop if a satellite does not rejoin in time.
+    #[arg(long, default_value_t = false)]
+    abort_on_failure: bool,
+
+    /// what to do, reboot the mesh if omitted.
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() {
-    let args = Args::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Watch internet connectivity, reboot the mesh only when it's lost.
+    Watch(WatchArgs),
 
-    let base_url = Url::parse(args.url.as_str()).unwrap();
+    /// Run scheduled profiles from a config file, in place of cron.
+    Daemon(DaemonArgs),
 
-    let mut api = MercusysHTTP::new(base_url);
-    api.set_logging_enabled(args.verbose);
-    api.login(&args.user, &args.password);
+    /// Serve a local REST API, sharing a single router session.
+    Serve(ServeArgs),
+
+    /// Serve Prometheus metrics about the mesh nodes.
+    Exporter(ExporterArgs),
+
+    /// List clients (phones, laptops...) connected to the mesh.
+    Clients(ClientsArgs),
+
+    /// Read or update the main wireless network (SSID, passphrase, bands).
+    Wifi(WifiArgs),
+
+    /// Manage the guest network, optionally turning it off again later.
+    Guest(GuestArgs),
+
+    /// Firmware inventory across the mesh, and controlled upgrades.
+    Firmware(FirmwareArgs),
+
+    /// Save, list and restore router configuration backups.
+    Backup(BackupArgs),
+
+    /// Print, follow or clear the router system log, optionally forwarding it to syslog.
+    Logs(LogsArgs),
+
+    /// Block or unblock clients by MAC address, optionally only for a while.
+    Access(AccessArgs),
+
+    /// Parental control profiles: devices, time limits, bedtime and pausing.
+    Profile(ProfileArgs),
+
+    /// Read or set the router's built-in reboot schedule.
+    Schedule(ScheduleArgs),
+
+    /// Internet connection status, and reconnecting it without a reboot.
+    Wan(WanArgs),
+
+    /// Set the nickname (location) of a mesh node.
+    Rename(RenameArgs),
+
+    /// Run a speed test from the main unit.
+    Speedtest(SpeedtestArgs),
+
+    /// Promote another node to main unit, and wait for the mesh to settle.
+    SetGateway(SetGatewayArgs),
+
+    /// Remove a satellite, or add a new one to the mesh.
+    Node(NodeArgs),
+
+    /// Change the admin password, and update --password-file.
+    Passwd(PasswdArgs),
+
+    /// DHCP address reservations, with import/export to JSON or CSV.
+    Reservations(ReservationsArgs),
+
+    /// Port forwarding rules, and UPnP mappings.
+    Ports(PortsArgs),
 
+    /// Ping or traceroute from the router itself, to tell ISP from LAN problems.
+    Diag(DiagArgs),
+
+    /// Status lights on/off, and night mode to turn them off at night.
+    Led(LedArgs),
+
+    /// Read the operation mode, or switch between router and access point.
+    Mode(ModeArgs),
+}
+
+fn reboot_mesh(api: &mut MercusysHTTP, args: &Args) -> Result<(), String> {
     if args.dry_run {
+        if args.rolling {
+            if let Ok(devices) = api.get_device_list() {
+                for device in reboot_order(&devices.result.device_list) {
+                    eprintln!("would reboot {} ({})", device.mac, device.role);
+                }
+            }
+        }
+
         eprintln!("dry run mode, exit... {:?}", api.logout());
 
-        return;
+        return Ok(());
+    }
+
+    if args.rolling {
+        let options = RollingRebootOptions {
+            node_timeout: Duration::from_secs(args.node_timeout),
+            abort_on_failure: args.abort_on_failure,
+            ..Default::default()
+        };
+
+        let report = rolling_reboot(api, &options)
+            .map_err(|err| format!("rolling reboot failed: {}", err))?;
+        if !report.is_success() {
+            return Err(format!(
+                "rolling reboot failed for: {}",
+                report.failed.join(", ")
+            ));
+        }
+
+        eprintln!(
+            "rolling reboot success, main unit wait timeout of {:?}",
+            report.main_reboot_time
+        );
+        return Ok(());
     }
 
-    let reboot_response = api.reboot_whole_mesh();
+    let reboot_response = api
+        .reboot_whole_mesh()
+        .map_err(|err| format!("reboot failed: {}", err))?;
     {
         let timeout = reboot_response.result.reboot_time;
         eprintln!("reboot success with wait timeout of {}", timeout);
     }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = Args::parse();
+
+    let base_url = Url::parse(args.url.as_str()).unwrap();
+
+    // Catch config mistakes before touching the router.
+    let daemon_config = match &args.command {
+        Some(Command::Daemon(daemon_args)) => match load_config(&daemon_args.config) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => None,
+    };
+
+    // Local only, no need to log in.
+    if let Some(Command::Backup(BackupArgs {
+        action: BackupAction::List,
+        dir,
+    })) = &args.command
+    {
+        return match list_backups(dir) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut api = MercusysHTTP::new(base_url);
+    api.set_logging_enabled(args.verbose);
+
+    // Long running modes: the router might just be unreachable right now,
+    //   they will login again when needed.
+    let long_running = matches!(
+        args.command,
+        Some(
+            Command::Watch(_)
+                | Command::Daemon(_)
+                | Command::Serve(_)
+                | Command::Exporter(_)
+                | Command::Logs(LogsArgs { follow: true, .. })
+        )
+    );
+    let password = match (&args.password, &args.password_file) {
+        (Some(password), _) => password.clone(),
+        (None, Some(path)) => match read_password_file(path) {
+            Ok(password) => password,
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        },
+        // Not required by clap, `backup list` above works without.
+        (None, None) => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the router admin password is required, use --password or --password-file",
+            )
+            .exit(),
+    };
+
+    if let Err(err) = api.login(&args.user, &password) {
+        eprintln!("login failed: {}", err);
+        if !long_running {
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let result = match args.command.take() {
+        None => reboot_mesh(&mut api, &args),
+        Some(Command::Watch(watch_args)) => {
+            Watchdog::new(watch_args, args.dry_run).run(&mut api);
+            Ok(())
+        }
+        Some(Command::Daemon(_)) => run_daemon(&mut api, daemon_config.unwrap(), args.dry_run),
+        Some(Command::Serve(serve_args)) => serve(api, &serve_args, args.dry_run),
+        Some(Command::Exporter(exporter_args)) => run_exporter(&mut api, &exporter_args),
+        Some(Command::Clients(clients_args)) => list_clients(&mut api, &clients_args),
+        Some(Command::Wifi(wifi_args)) => run_wifi(&mut api, &wifi_args, args.dry_run),
+        Some(Command::Guest(guest_args)) => run_guest(&mut api, &guest_args, args.dry_run),
+        Some(Command::Firmware(firmware_args)) => {
+            run_firmware(&mut api, &firmware_args, args.dry_run)
+        }
+        Some(Command::Backup(backup_args)) => run_backup(&mut api, &backup_args, args.dry_run),
+        Some(Command::Logs(logs_args)) => run_logs(&mut api, &logs_args, args.dry_run),
+        Some(Command::Access(access_args)) => run_access(&mut api, &access_args, args.dry_run),
+        Some(Command::Profile(profile_args)) => run_profile(&mut api, &profile_args, args.dry_run),
+        Some(Command::Schedule(schedule_args)) => {
+            run_schedule(&mut api, &schedule_args, args.dry_run)
+        }
+        Some(Command::Wan(wan_args)) => run_wan(&mut api, &wan_args, args.dry_run),
+        Some(Command::Rename(rename_args)) => run_rename(&mut api, &rename_args, args.dry_run),
+        Some(Command::Speedtest(speedtest_args)) => run_speedtest(&mut api, &speedtest_args),
+        Some(Command::SetGateway(gateway_args)) => {
+            run_set_gateway(&mut api, &gateway_args, args.dry_run)
+        }
+        Some(Command::Node(node_args)) => run_node(&mut api, &node_args, args.dry_run),
+        Some(Command::Passwd(passwd_args)) => run_passwd(
+            &mut api,
+            &passwd_args,
+            &args.user,
+            &password,
+            args.password_file.as_deref(),
+            args.dry_run,
+        ),
+        Some(Command::Reservations(reservations_args)) => {
+            run_reservations(&mut api, &reservations_args, args.dry_run)
+        }
+        Some(Command::Ports(ports_args)) => run_ports(&mut api, &ports_args, args.dry_run),
+        Some(Command::Diag(diag_args)) => run_diag(&mut api, &diag_args),
+        Some(Command::Led(led_args)) => run_led(&mut api, &led_args, args.dry_run),
+        Some(Command::Mode(mode_args)) => run_mode(&mut api, &mode_args, args.dry_run),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
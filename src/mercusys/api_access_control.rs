@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::decode_text;
+use super::mac_address::MacAddress;
+
+pub type AccessModeResponse = MercusysAPIResponse<AccessModeSettings>;
+pub type AccessListResponse = MercusysAPIResponse<AccessListResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessModeRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<AccessModeSettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessModeSettings {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// "block": listed clients are cut off.
+    /// "allow": only listed clients get access.
+    #[serde(default)]
+    pub access_mode: String,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AccessModeSettings {
+    pub fn is_allowlist(&self) -> bool {
+        self.access_mode == "allow"
+    }
+
+    /// Whether a client belongs on the list of the current mode: blocked
+    ///   clients in blocklist mode, allowed ones in allowlist mode.
+    pub fn wants_listed(&self, allowed: bool) -> bool {
+        allowed == self.is_allowlist()
+    }
+
+    /// Form holding the list the current mode uses.
+    pub fn list_form(&self) -> &'static str {
+        if self.is_allowlist() {
+            "allow_list"
+        } else {
+            "block_list"
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessListRequestBody {
+    /// "read", "add" or "remove"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<AccessListResult>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessListResult {
+    #[serde(default)]
+    pub client_list: Vec<AccessListItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessListItem {
+    pub mac: MacAddress,
+
+    /// Base64 encoded by the router.
+    #[serde(default)]
+    pub name: String,
+}
+
+impl AccessListItem {
+    pub fn hostname(&self) -> String {
+        decode_text(&self.name)
+    }
+}
+
+#[test]
+fn access_mode_roundtrip_test() {
+    let json = r#"{"enable":true,"access_mode":"block","schedule":{"enable":false}}"#;
+
+    let settings: AccessModeSettings = serde_json::from_str(json).unwrap();
+    assert!(settings.extra.contains_key("schedule"));
+    assert_eq!(serde_json::to_string(&settings).unwrap(), json);
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type ChangePasswordResponse = MercusysAPIResponse<ChangePasswordResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangePasswordRequestBody {
+    /// Always "write"
+    pub operation: String,
+
+    pub params: ChangePasswordParam,
+}
+
+/// Both RSA encrypted with the `login keys` password key, like at login.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangePasswordParam {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangePasswordResult {}
+
+/// Same rules as the web UI.
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if !(6..=32).contains(&password.len()) {
+        return Err("password must be 6 to 32 characters long".into());
+    }
+    if !password.chars().all(|c| c.is_ascii_graphic()) {
+        return Err("password must be printable ASCII, without spaces".into());
+    }
+    Ok(())
+}
+
+#[test]
+fn validate_password_test() {
+    assert!(validate_password("s3cret!").is_ok());
+    assert!(validate_password("short").is_err());
+    assert!(validate_password("with space").is_err());
+    assert!(validate_password("pässword").is_err());
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestoreResponseResult {
+    /// The router reboots to apply the restored configuration.
+    #[serde(default)]
+    pub reboot_time: i64,
+}
+pub type RestoreResponse = MercusysAPIResponse<RestoreResponseResult>;
+
+/// What the router sends instead of a backup when it refuses, e.g.
+///   `{"error_code": -40401}`. An actual backup is never JSON.
+pub type BackupErrorResponse = MercusysAPIResponse<serde_json::Value>;
+
+#[test]
+fn backup_error_response_test() {
+    let resp: BackupErrorResponse =
+        serde_json::from_slice(br#"{"success": false, "error_code": -40401}"#).unwrap();
+    assert_eq!(resp.error_code, -40401);
+
+    let backup = [0x8f_u8, 0x02, 0x7b, 0x00, 0xff];
+    assert!(serde_json::from_slice::<BackupErrorResponse>(&backup).is_err());
+}
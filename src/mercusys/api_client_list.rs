@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::decode_text;
+
+pub type ClientListResponse = MercusysAPIResponse<ClientListResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientListResult {
+    #[serde(default)]
+    pub client_list: Vec<SingleClientItem>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SingleClientItem {
+    #[serde(default)]
+    pub mac: String,
+    #[serde(default)]
+    pub ip: String,
+
+    /// Hostname, base64 encoded by the router.
+    #[serde(default)]
+    pub name: String,
+
+    /// "band2_4", "band5" or "wired"
+    #[serde(default)]
+    pub connection_type: String,
+    #[serde(default)]
+    pub wire_type: String,
+
+    /// MAC address of the mesh node the client is attached to.
+    #[serde(default)]
+    pub access_host: String,
+
+    /// Link rate in Mbps.
+    #[serde(default)]
+    pub tx_rate: i64,
+    #[serde(default)]
+    pub rx_rate: i64,
+
+    /// Seconds since the client connected.
+    #[serde(default)]
+    pub online_time: i64,
+    #[serde(default)]
+    pub online: bool,
+
+    #[serde(default)]
+    pub client_type: String,
+}
+
+impl SingleClientItem {
+    /// Decoded hostname, or the raw value if it's not base64.
+    pub fn hostname(&self) -> String {
+        decode_text(&self.name)
+    }
+
+    /// "2.4", "5" or "wired"
+    pub fn band(&self) -> &str {
+        match self.connection_type.as_str() {
+            "band2_4" => "2.4",
+            "band5" => "5",
+            _ if self.wire_type == "wired" => "wired",
+            other => other,
+        }
+    }
+}
+
+#[test]
+fn band_test() {
+    let client = |connection_type: &str, wire_type: &str| SingleClientItem {
+        connection_type: connection_type.into(),
+        wire_type: wire_type.into(),
+        ..Default::default()
+    };
+
+    assert_eq!(client("band2_4", "wireless").band(), "2.4");
+    assert_eq!(client("band5", "wireless").band(), "5");
+    assert_eq!(client("", "wired").band(), "wired");
+    assert_eq!(client("band6", "wireless").band(), "band6");
+}
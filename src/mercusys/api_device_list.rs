@@ -1,8 +1,71 @@
 use serde::{Deserialize, Serialize};
 
 use super::api_response::MercusysAPIResponse;
+use super::encoding::decode_text;
 
 pub type DeviceListResponse = MercusysAPIResponse<DeviceListResult>;
+pub type DeviceUpdateResponse = MercusysAPIResponse<SingleDeviceItem>;
+pub type DeviceRemoveResponse = MercusysAPIResponse<DeviceListResult>;
+pub type OnboardingResponse = MercusysAPIResponse<OnboardingStatus>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceRemoveRequestBody {
+    /// Always "remove"
+    pub operation: String,
+
+    pub params: DeviceRemoveParam,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceRemoveParam {
+    pub mac: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnboardingRequestBody {
+    /// "start" or "stop"
+    pub operation: String,
+}
+
+/// Whether the main unit is looking for new satellites.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnboardingStatus {
+    /// "idle" or "scanning"
+    #[serde(default)]
+    pub status: String,
+
+    /// Seconds left before the router stops looking on its own.
+    #[serde(default)]
+    pub remaining_time: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceRequestBody {
+    /// Always "write"
+    pub operation: String,
+
+    pub params: DeviceUpdateParam,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceUpdateParam {
+    pub mac: String,
+
+    /// Shown as the node's location in the app, e.g. "Garage". Base64 encoded.
+    pub nickname: String,
+}
+
+/// Checked on the plain text, before it gets encoded.
+pub fn validate_nickname(nickname: &str) -> Result<(), String> {
+    let length = nickname.chars().count();
+    if length == 0 || length > 32 {
+        return Err("nickname must be 1 to 32 characters long".into());
+    }
+    if nickname.chars().any(char::is_control) {
+        return Err("nickname can't contain control characters".into());
+    }
+    Ok(())
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DeviceListResult {
@@ -74,6 +137,39 @@ pub struct SingleDeviceItem {
     pub device_type: String,
 }
 
+impl SingleDeviceItem {
+    /// The main unit, the one talking to the modem.
+    pub fn is_main(&self) -> bool {
+        self.role == "master"
+    }
+
+    /// Node is part of the mesh (again).
+    pub fn is_connected(&self) -> bool {
+        self.group_status == "connected"
+    }
+
+    /// Decoded nickname, or the raw value if it's not base64.
+    pub fn nickname(&self) -> String {
+        decode_text(&self.nickname)
+    }
+
+    /// Node reports working internet access.
+    pub fn is_online(&self) -> bool {
+        self.inet_status == "online"
+    }
+}
+
+/// A connected node with only the fields most tests care about.
+#[cfg(test)]
+pub fn make_test_device(mac: &str, role: &str) -> SingleDeviceItem {
+    SingleDeviceItem {
+        mac: mac.into(),
+        role: role.into(),
+        group_status: "connected".into(),
+        ..Default::default()
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SignalLevel {
     #[serde(default)]
@@ -81,3 +177,18 @@ pub struct SignalLevel {
     #[serde(default)]
     pub band5: String,
 }
+
+#[test]
+fn nickname_test() {
+    let device = SingleDeviceItem {
+        nickname: "R2FyYWdl".into(),
+        ..Default::default()
+    };
+    assert_eq!(device.nickname(), "Garage");
+
+    assert!(validate_nickname("Garage").is_ok());
+    assert!(validate_nickname("Küche").is_ok());
+    assert!(validate_nickname("").is_err());
+    assert!(validate_nickname(&"x".repeat(33)).is_err());
+    assert!(validate_nickname("two\nlines").is_err());
+}
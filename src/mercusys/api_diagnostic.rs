@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type DiagnosticResponse = MercusysAPIResponse<DiagnosticStatus>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Ping,
+    Traceroute,
+}
+
+impl DiagnosticKind {
+    /// Form of the diagnostic endpoint.
+    pub fn form(self) -> &'static str {
+        match self {
+            DiagnosticKind::Ping => "ping",
+            DiagnosticKind::Traceroute => "tracert",
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticRequestBody {
+    /// "start" or "read"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<DiagnosticParam>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticParam {
+    /// Hostname or IP address.
+    pub host: String,
+
+    /// Ping only, echo requests to send.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+
+    /// Traceroute only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_hops: Option<u32>,
+}
+
+impl DiagnosticParam {
+    pub fn validate(&self) -> Result<(), String> {
+        // Passed on to ping/traceroute on the router, must not look like an option.
+        let host_ok = !self.host.is_empty()
+            && !self.host.starts_with('-')
+            && self.host.len() <= 253
+            && self
+                .host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'));
+        if !host_ok {
+            return Err(format!("invalid host \"{}\"", self.host));
+        }
+        if matches!(self.count, Some(count) if !(1..=50).contains(&count)) {
+            return Err("ping count must be between 1 and 50".into());
+        }
+        if matches!(self.max_hops, Some(hops) if !(1..=64).contains(&hops)) {
+            return Err("max hops must be between 1 and 64".into());
+        }
+        Ok(())
+    }
+}
+
+/// Last (or running) diagnostic, with the raw output of the tool on the router.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticStatus {
+    /// "idle", "running", "done" or "failed"
+    #[serde(default)]
+    pub status: String,
+
+    #[serde(default)]
+    pub output: String,
+}
+
+impl DiagnosticStatus {
+    pub fn is_running(&self) -> bool {
+        self.status == "running"
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == "done"
+    }
+}
+
+/// Summary of a finished ping.
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct PingResult {
+    pub transmitted: u32,
+    pub received: u32,
+
+    /// Milliseconds, missing when nothing came back.
+    pub rtt_min: Option<f64>,
+    pub rtt_avg: Option<f64>,
+    pub rtt_max: Option<f64>,
+}
+
+impl PingResult {
+    /// From busybox ping output, e.g.
+    ///   "4 packets transmitted, 4 packets received, 0% packet loss"
+    ///   "round-trip min/avg/max = 10.1/12.3/15.0 ms"
+    pub fn parse(output: &str) -> Option<PingResult> {
+        let mut result: Option<PingResult> = None;
+
+        for line in output.lines() {
+            if line.contains("packets transmitted") {
+                let numbers: Vec<u32> = line
+                    .split(',')
+                    .filter_map(|part| part.split_whitespace().next()?.parse().ok())
+                    .collect();
+                if let [transmitted, received, ..] = numbers[..] {
+                    result = Some(PingResult {
+                        transmitted,
+                        received,
+                        ..Default::default()
+                    });
+                }
+            } else if let Some((_, values)) = line.split_once(" = ") {
+                let rtt: Vec<f64> = values
+                    .trim_end_matches("ms")
+                    .trim()
+                    .split('/')
+                    .filter_map(|value| value.parse().ok())
+                    .collect();
+                if let (Some(result), &[min, avg, max, ..]) = (result.as_mut(), &rtt[..]) {
+                    result.rtt_min = Some(min);
+                    result.rtt_avg = Some(avg);
+                    result.rtt_max = Some(max);
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        if self.transmitted == 0 {
+            return 100.0;
+        }
+        100.0 * f64::from(self.transmitted - self.received.min(self.transmitted))
+            / f64::from(self.transmitted)
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.received > 0
+    }
+}
+
+/// One line of traceroute output.
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct TracerouteHop {
+    pub hop: u32,
+
+    /// None when the hop did not answer ("*").
+    pub address: Option<String>,
+
+    /// Milliseconds, one per answered probe.
+    pub rtt: Vec<f64>,
+}
+
+impl TracerouteHop {
+    /// From busybox traceroute output, e.g.
+    ///   " 1  192.168.1.1  1.234 ms  1.101 ms  0.998 ms"
+    ///   " 2  *  *  *"
+    pub fn parse_all(output: &str) -> Vec<TracerouteHop> {
+        output.lines().filter_map(TracerouteHop::parse).collect()
+    }
+
+    fn parse(line: &str) -> Option<TracerouteHop> {
+        let mut words = line.split_whitespace().peekable();
+        let hop = words.next()?.parse().ok()?;
+
+        let mut address = None;
+        let mut rtt = vec![];
+        while let Some(word) = words.next() {
+            match word {
+                "*" | "ms" => {}
+                _ if word.starts_with('(') => {}
+                _ => match word.parse::<f64>() {
+                    Ok(value) if words.peek() == Some(&"ms") => rtt.push(value),
+                    _ if address.is_none() => address = Some(word.to_string()),
+                    _ => {}
+                },
+            }
+        }
+
+        Some(TracerouteHop { hop, address, rtt })
+    }
+}
+
+#[test]
+fn ping_result_parse_test() {
+    let output = "PING 1.1.1.1 (1.1.1.1): 56 data bytes\n\
+        64 bytes from 1.1.1.1: seq=0 ttl=57 time=10.100 ms\n\
+        \n\
+        --- 1.1.1.1 ping statistics ---\n\
+        4 packets transmitted, 3 packets received, 25% packet loss\n\
+        round-trip min/avg/max = 10.100/12.300/15.000 ms\n";
+    let result = PingResult::parse(output).unwrap();
+    assert_eq!(result.transmitted, 4);
+    assert_eq!(result.received, 3);
+    assert_eq!(result.loss_percent(), 25.0);
+    assert_eq!(result.rtt_avg, Some(12.3));
+    assert!(result.is_reachable());
+
+    let output = "--- 10.0.0.1 ping statistics ---\n\
+        4 packets transmitted, 0 packets received, 100% packet loss\n";
+    let result = PingResult::parse(output).unwrap();
+    assert!(!result.is_reachable());
+    assert_eq!(result.rtt_min, None);
+
+    assert_eq!(PingResult::parse("ping: bad address 'nope'"), None);
+}
+
+#[test]
+fn traceroute_hop_parse_test() {
+    let output = "traceroute to 1.1.1.1 (1.1.1.1), 30 hops max, 38 byte packets\n \
+        1  192.168.1.1  1.234 ms  1.101 ms  0.998 ms\n \
+        2  *  *  *\n \
+        3  one.one.one.one (1.1.1.1)  9.8 ms  *  10.2 ms\n";
+    let hops = TracerouteHop::parse_all(output);
+    assert_eq!(hops.len(), 3);
+    assert_eq!(hops[0].address.as_deref(), Some("192.168.1.1"));
+    assert_eq!(hops[0].rtt, vec![1.234, 1.101, 0.998]);
+    assert_eq!(hops[1].address, None);
+    assert!(hops[1].rtt.is_empty());
+    assert_eq!(hops[2].address.as_deref(), Some("one.one.one.one"));
+    assert_eq!(hops[2].rtt, vec![9.8, 10.2]);
+}
+
+#[test]
+fn diagnostic_param_validate_test() {
+    let param = |host: &str| DiagnosticParam {
+        host: host.into(),
+        count: Some(4),
+        max_hops: None,
+    };
+
+    assert!(param("1.1.1.1").validate().is_ok());
+    assert!(param("one-one.example.com").validate().is_ok());
+    assert!(param("2606:4700::1111").validate().is_ok());
+    assert!(param("").validate().is_err());
+    assert!(param("-f").validate().is_err());
+    assert!(param("--help").validate().is_err());
+    assert!(param("1.1.1.1; reboot").validate().is_err());
+    assert!(DiagnosticParam {
+        count: Some(0),
+        ..param("1.1.1.1")
+    }
+    .validate()
+    .is_err());
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type FirmwareCheckResponse = MercusysAPIResponse<FirmwareCheckResult>;
+pub type FirmwareUpgradeResponse = MercusysAPIResponse<FirmwareUpgradeResult>;
+pub type FirmwareStatusResponse = MercusysAPIResponse<FirmwareStatusResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareRequestBody {
+    /// "check", "upgrade" or "read"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<FirmwareRequestParam>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareRequestParam {
+    #[serde(rename = "mac_list")]
+    pub mac_address_list: Vec<FirmwareMacAddress>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareMacAddress {
+    pub mac: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareCheckResult {
+    #[serde(default)]
+    pub device_list: Vec<OnlineFirmwareItem>,
+}
+
+/// Firmware available online for a single node.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnlineFirmwareItem {
+    #[serde(default)]
+    pub mac: String,
+    #[serde(default)]
+    pub fw_ver: String,
+    #[serde(default)]
+    pub release_date: String,
+    #[serde(default)]
+    pub release_note: String,
+    #[serde(default)]
+    pub need_upgrade: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareUpgradeResult {
+    /// Rough estimate by the router, in seconds.
+    #[serde(default)]
+    pub upgrade_time: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareStatusResult {
+    #[serde(default)]
+    pub device_list: Vec<FirmwareStatusItem>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirmwareStatusItem {
+    #[serde(default)]
+    pub mac: String,
+
+    /// "idle", "downloading", "upgrading", "done" or "failed"
+    #[serde(default)]
+    pub upgrade_status: String,
+
+    /// Percent.
+    #[serde(default)]
+    pub progress: i64,
+}
+
+impl FirmwareStatusItem {
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self.upgrade_status.as_str(), "downloading" | "upgrading")
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.upgrade_status == "failed"
+    }
+}
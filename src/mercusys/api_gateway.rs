@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type SetGatewayResponse = MercusysAPIResponse<SetGatewayResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetGatewayRequestBody {
+    /// Always "write"
+    pub operation: String,
+
+    pub params: SetGatewayParam,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetGatewayParam {
+    /// Node to become the main unit.
+    pub mac: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetGatewayResult {
+    /// Seconds the router expects the mesh to take to settle.
+    #[serde(default)]
+    pub wait_time: i64,
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::api_wireless::WirelessNetwork;
+
+pub type GuestResponse = MercusysAPIResponse<GuestSettings>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuestRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<GuestSettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuestSettings {
+    /// Shared by both bands. Its `extra` also keeps the guest fields we
+    ///   don't know about, a second flattened map here would duplicate them.
+    #[serde(flatten)]
+    pub network: WirelessNetwork,
+
+    /// Kbps, 0 for unlimited.
+    #[serde(default)]
+    pub upload_limit: i64,
+
+    /// Kbps, 0 for unlimited.
+    #[serde(default)]
+    pub download_limit: i64,
+
+    /// Guests can't reach each other, nor the main network.
+    #[serde(default)]
+    pub isolation: bool,
+}
+
+impl GuestSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.network.enable {
+            return Ok(());
+        }
+
+        if self.upload_limit < 0 || self.download_limit < 0 {
+            return Err("bandwidth limit can't be negative".into());
+        }
+
+        self.network.validate()
+    }
+}
+
+#[test]
+fn guest_settings_roundtrip_test() {
+    let json = r#"{"enable":true,"ssid":"","password":"","encryption":"psk","hide_ssid":false,"upload_limit":0,"download_limit":0,"isolation":true,"portal":{"enable":false}}"#;
+
+    let settings: GuestSettings = serde_json::from_str(json).unwrap();
+    assert!(settings.network.extra.contains_key("portal"));
+    assert_eq!(
+        serde_json::to_string(&settings)
+            .unwrap()
+            .matches("portal")
+            .count(),
+        1
+    );
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type LedResponse = MercusysAPIResponse<LedSettings>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<LedSettings>,
+}
+
+/// Status lights of every node in the mesh.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedSettings {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default)]
+    pub night_mode: NightMode,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Lights off between `start_time` and `end_time`, even when enabled.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NightMode {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Router local time, "HH:MM".
+    #[serde(default)]
+    pub start_time: String,
+
+    /// Router local time, "HH:MM", may be on the next day.
+    #[serde(default)]
+    pub end_time: String,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl LedSettings {
+    /// Checked before anything gets written to the router.
+    pub fn validate(&self) -> Result<(), String> {
+        let night_mode = &self.night_mode;
+        if !night_mode.enable {
+            return Ok(());
+        }
+
+        for time in [&night_mode.start_time, &night_mode.end_time] {
+            if chrono::NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                return Err(format!("invalid time \"{}\", expected HH:MM", time));
+            }
+        }
+        if night_mode.start_time == night_mode.end_time {
+            return Err("night mode must start and end at different times".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn led_settings_validate_test() {
+    let mut settings = LedSettings {
+        enable: true,
+        night_mode: NightMode {
+            enable: true,
+            start_time: "22:00".into(),
+            end_time: "07:00".into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(settings.validate().is_ok());
+
+    settings.night_mode.end_time = "22:00".into();
+    assert!(settings.validate().is_err());
+
+    settings.night_mode.end_time = "7am".into();
+    assert!(settings.validate().is_err());
+
+    settings.night_mode.enable = false;
+    assert!(settings.validate().is_ok());
+}
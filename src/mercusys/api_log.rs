@@ -0,0 +1,176 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type LogResponse = MercusysAPIResponse<LogResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRequestBody {
+    /// "read" or "clear"
+    pub operation: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogResult {
+    /// Raw lines, oldest first, e.g.
+    ///   "2024-01-06 12:34:56 DHCPD NOTICE DHCPS:Send ACK to 192.168.68.104"
+    #[serde(default)]
+    pub log_list: Vec<String>,
+}
+
+impl LogResult {
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.log_list
+            .iter()
+            .map(|line| LogEntry::parse(line))
+            .collect()
+    }
+}
+
+/// Syslog severities, most severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Emergency,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    /// Numerical severity, as used by syslog.
+    pub fn severity(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "emerg" | "emergency" => Ok(LogLevel::Emergency),
+            "alert" => Ok(LogLevel::Alert),
+            "crit" | "critical" => Ok(LogLevel::Critical),
+            "err" | "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warning),
+            "notice" => Ok(LogLevel::Notice),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("unknown log level \"{}\"", s)),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Emergency => "EMERG",
+            LogLevel::Alert => "ALERT",
+            LogLevel::Critical => "CRIT",
+            LogLevel::Error => "ERROR",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Notice => "NOTICE",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Router local time, `None` if the line had an unexpected format.
+    pub time: Option<NaiveDateTime>,
+    pub level: LogLevel,
+    pub module: String,
+    pub message: String,
+
+    /// Line as returned by the router.
+    pub raw: String,
+}
+
+impl LogEntry {
+    /// Parse a "DATE TIME MODULE LEVEL MESSAGE" line. Lines in another format
+    ///   are kept whole as an info message.
+    pub fn parse(line: &str) -> LogEntry {
+        let fallback = || LogEntry {
+            time: None,
+            level: LogLevel::Info,
+            module: "".into(),
+            message: line.trim().into(),
+            raw: line.into(),
+        };
+
+        let mut parts = line.trim().splitn(5, ' ');
+        let (date, time, module, level) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(date), Some(time), Some(module), Some(level)) => (date, time, module, level),
+                _ => return fallback(),
+            };
+
+        let time = match NaiveDateTime::parse_from_str(
+            format!("{} {}", date, time).as_str(),
+            "%Y-%m-%d %H:%M:%S",
+        ) {
+            Ok(time) => time,
+            Err(_) => return fallback(),
+        };
+        let level = match level.parse() {
+            Ok(level) => level,
+            Err(_) => return fallback(),
+        };
+
+        LogEntry {
+            time: Some(time),
+            level,
+            module: module.into(),
+            message: parts.next().unwrap_or("").into(),
+            raw: line.into(),
+        }
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(
+                f,
+                "{} {:<7} {:<8} {}",
+                time.format("%Y-%m-%d %H:%M:%S"),
+                self.level,
+                self.module,
+                self.message
+            ),
+            None => write!(f, "{}", self.raw),
+        }
+    }
+}
+
+#[test]
+fn log_entry_parse_test() {
+    let entry =
+        LogEntry::parse("2024-01-06 12:34:56 DHCPD NOTICE DHCPS:Send ACK to 192.168.68.104");
+    assert_eq!(
+        entry.time.unwrap().format("%H:%M:%S").to_string(),
+        "12:34:56"
+    );
+    assert_eq!(entry.level, LogLevel::Notice);
+    assert_eq!(entry.module, "DHCPD");
+    assert_eq!(entry.message, "DHCPS:Send ACK to 192.168.68.104");
+
+    let entry = LogEntry::parse("something else");
+    assert_eq!(entry.time, None);
+    assert_eq!(entry.level, LogLevel::Info);
+    assert_eq!(entry.message, "something else");
+
+    assert!(LogLevel::Error < LogLevel::Warning);
+    assert_eq!(LogLevel::Warning.severity(), 4);
+}
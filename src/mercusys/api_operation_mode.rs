@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type OperationModeResponse = MercusysAPIResponse<OperationModeResult>;
+
+pub const OPERATION_MODES: [&str; 2] = ["router", "ap"];
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationModeRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<OperationModeParam>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationModeParam {
+    /// "router" or "ap"
+    pub mode: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationModeResult {
+    /// "router": gateway with DHCP and NAT.
+    /// "ap": access point behind another router.
+    #[serde(default)]
+    pub mode: String,
+
+    /// Seconds the router expects the switch to take, on write.
+    #[serde(default)]
+    pub wait_time: i64,
+}
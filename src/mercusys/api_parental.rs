@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::decode_text;
+use super::mac_address::MacAddress;
+
+pub type ParentalProfileListResponse = MercusysAPIResponse<ParentalProfileListResult>;
+pub type ParentalProfileResponse = MercusysAPIResponse<ParentalProfile>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentalRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<ParentalProfile>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentalProfileListResult {
+    #[serde(default)]
+    pub profile_list: Vec<ParentalProfile>,
+}
+
+impl ParentalProfileListResult {
+    /// Profile by name, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&ParentalProfile> {
+        self.profile_list
+            .iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentalProfile {
+    #[serde(default)]
+    pub profile_id: i64,
+
+    /// Base64 encoded by the router.
+    #[serde(default, rename = "name")]
+    pub encoded_name: String,
+
+    /// Devices the profile applies to, as the router lists them. Entries
+    ///   that aren't a MAC address are kept, and written back unchanged.
+    #[serde(default)]
+    pub client_list: Vec<String>,
+
+    #[serde(default)]
+    pub time_limit: TimeLimit,
+
+    #[serde(default)]
+    pub bedtime: Bedtime,
+
+    /// Internet access cut off right now, until resumed.
+    #[serde(default)]
+    pub internet_paused: bool,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ParentalProfile {
+    pub fn name(&self) -> String {
+        decode_text(&self.encoded_name)
+    }
+
+    pub fn has_client(&self, mac: MacAddress) -> bool {
+        self.client_list.iter().any(|other| mac.matches(other))
+    }
+
+    pub fn add_client(&mut self, mac: MacAddress) {
+        if !self.has_client(mac) {
+            self.client_list.push(mac.to_string());
+        }
+    }
+
+    pub fn remove_clients(&mut self, macs: &[MacAddress]) {
+        self.client_list
+            .retain(|other| !macs.iter().any(|mac| mac.matches(other)));
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeLimit {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Online time allowed per day, in minutes.
+    #[serde(default)]
+    pub daily_minutes: i64,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// No internet between `start_time` and `end_time` ("HH:MM"), which may span midnight.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bedtime {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default)]
+    pub start_time: String,
+    #[serde(default)]
+    pub end_time: String,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[test]
+fn client_list_test() {
+    let mac = |mac: &str| mac.parse::<MacAddress>().unwrap();
+    let mut profile: ParentalProfile =
+        serde_json::from_str(r#"{"client_list":["AA-BB-CC-DD-EE-FF","","tablet"]}"#).unwrap();
+    assert!(profile.has_client(mac("aa:bb:cc:dd:ee:ff")));
+
+    profile.add_client(mac("AA-BB-CC-DD-EE-FF"));
+    profile.add_client(mac("aa:bb:cc:dd:ee:01"));
+    profile.remove_clients(&[mac("aa:bb:cc:dd:ee:ff")]);
+
+    // Whatever the router had that isn't a MAC address stays.
+    assert_eq!(profile.client_list, vec!["", "tablet", "AA-BB-CC-DD-EE-01"]);
+}
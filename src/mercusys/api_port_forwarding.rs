@@ -0,0 +1,342 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::decode_text;
+
+pub type PortForwardingListResponse = MercusysAPIResponse<PortForwardingListResult>;
+pub type UpnpSettingsResponse = MercusysAPIResponse<UpnpSettings>;
+pub type UpnpListResponse = MercusysAPIResponse<UpnpListResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForwardingRequestBody {
+    /// "read" or "add"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<PortForwardingRule>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForwardingRemoveRequestBody {
+    /// Always "remove"
+    pub operation: String,
+
+    pub params: PortForwardingRemoveParam,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForwardingRemoveParam {
+    pub id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForwardingListResult {
+    #[serde(default)]
+    pub rule_list: Vec<PortForwardingRule>,
+}
+
+/// "TCP", "UDP" or "ALL" (both).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    All,
+}
+
+impl Protocol {
+    pub fn overlaps(self, other: Protocol) -> bool {
+        self == other || self == Protocol::All || other == Protocol::All
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tcp" => Ok(Protocol::Tcp),
+            "udp" => Ok(Protocol::Udp),
+            "all" | "both" => Ok(Protocol::All),
+            _ => Err(format!(
+                "unknown protocol \"{}\", expected tcp, udp or all",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
+            Protocol::All => "ALL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single port, "8080", or an inclusive range, "8000-8010". The router
+///   sends single ports as JSON numbers too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "NumberOrText", into = "String")]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn count(&self) -> u32 {
+        u32::from(self.end - self.start) + 1
+    }
+
+    pub fn overlaps(&self, other: &PortRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl From<u16> for PortRange {
+    fn from(port: u16) -> Self {
+        PortRange {
+            start: port,
+            end: port,
+        }
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid port or port range \"{}\"", s);
+        let port = |p: &str| match p.trim().parse::<u16>() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(port) => Ok(port),
+        };
+
+        let range = match s.split_once('-') {
+            Some((start, end)) => PortRange {
+                start: port(start)?,
+                end: port(end)?,
+            },
+            None => port(s)?.into(),
+        };
+        if range.start > range.end {
+            return Err(invalid());
+        }
+        Ok(range)
+    }
+}
+
+/// Ports and IDs come as JSON numbers or strings, depending on the firmware.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(u64),
+    Text(String),
+}
+
+impl From<NumberOrText> for String {
+    fn from(value: NumberOrText) -> Self {
+        match value {
+            NumberOrText::Number(n) => n.to_string(),
+            NumberOrText::Text(s) => s,
+        }
+    }
+}
+
+impl TryFrom<NumberOrText> for PortRange {
+    type Error = String;
+
+    fn try_from(value: NumberOrText) -> Result<Self, Self::Error> {
+        String::from(value).parse()
+    }
+}
+
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    NumberOrText::deserialize(deserializer).map(String::from)
+}
+
+fn deserialize_port<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    let value = String::from(NumberOrText::deserialize(deserializer)?);
+    value
+        .trim()
+        .parse()
+        .map_err(|_| D::Error::custom(format!("invalid port \"{}\"", value)))
+}
+
+impl From<PortRange> for String {
+    fn from(range: PortRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortForwardingRule {
+    /// Assigned by the router, empty when adding.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_id",
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub id: String,
+
+    /// Base64 encoded by the router.
+    #[serde(default)]
+    pub name: String,
+
+    pub protocol: Protocol,
+    pub external_port: PortRange,
+    pub internal_port: PortRange,
+    pub internal_ip: Ipv4Addr,
+
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+fn default_enable() -> bool {
+    true
+}
+
+impl PortForwardingRule {
+    pub fn service_name(&self) -> String {
+        decode_text(&self.name)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.internal_port.count() != self.external_port.count() {
+            return Err(format!(
+                "internal port {} does not match the size of external range {}",
+                self.internal_port, self.external_port
+            ));
+        }
+        if self.internal_ip.is_unspecified() || self.internal_ip.is_broadcast() {
+            return Err(format!("invalid internal IP {}", self.internal_ip));
+        }
+        Ok(())
+    }
+
+    /// Whether both would take the same external port.
+    pub fn conflicts_with(&self, protocol: Protocol, external_port: &PortRange) -> bool {
+        self.protocol.overlaps(protocol) && self.external_port.overlaps(external_port)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpnpRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<UpnpSettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpnpSettings {
+    #[serde(default)]
+    pub enable: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpnpListResult {
+    #[serde(default)]
+    pub upnp_list: Vec<UpnpMapping>,
+}
+
+/// A mapping opened by a device on the LAN.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpnpMapping {
+    /// Base64 encoded by the router.
+    #[serde(default)]
+    pub app_description: String,
+
+    pub protocol: Protocol,
+    #[serde(deserialize_with = "deserialize_port")]
+    pub external_port: u16,
+    #[serde(deserialize_with = "deserialize_port")]
+    pub internal_port: u16,
+    pub internal_ip: Ipv4Addr,
+}
+
+impl UpnpMapping {
+    pub fn description(&self) -> String {
+        decode_text(&self.app_description)
+    }
+}
+
+#[test]
+fn port_forwarding_rule_test() {
+    let range: PortRange = "8000-8010".parse().unwrap();
+    assert_eq!(range.count(), 11);
+    assert_eq!(range.to_string(), "8000-8010");
+    assert_eq!("443".parse::<PortRange>(), Ok(443.into()));
+    assert!("8010-8000".parse::<PortRange>().is_err());
+    assert!("0".parse::<PortRange>().is_err());
+
+    let rule = PortForwardingRule {
+        id: "1".into(),
+        name: String::new(),
+        protocol: Protocol::Tcp,
+        external_port: range,
+        internal_port: "9000-9010".parse().unwrap(),
+        internal_ip: "192.168.68.10".parse().unwrap(),
+        enable: true,
+    };
+    assert!(rule.validate().is_ok());
+    assert!(rule.conflicts_with(Protocol::Tcp, &8010.into()));
+    assert!(rule.conflicts_with(Protocol::All, &"7990-8000".parse().unwrap()));
+    assert!(!rule.conflicts_with(Protocol::Udp, &8005.into()));
+    assert!(!rule.conflicts_with(Protocol::Tcp, &8011.into()));
+
+    let parsed: Vec<PortRange> = serde_json::from_str(r#"[443, "443", "8000-8010"]"#).unwrap();
+    assert_eq!(parsed, vec![443.into(), 443.into(), range]);
+    assert!(serde_json::from_str::<PortRange>("0").is_err());
+
+    let mismatched = PortForwardingRule {
+        internal_port: 9000.into(),
+        ..rule
+    };
+    assert!(mismatched.validate().is_err());
+}
+
+#[test]
+fn number_or_text_test() {
+    let rules: Vec<PortForwardingRule> = serde_json::from_str(
+        r#"[
+            {"id": 3, "protocol": "TCP", "external_port": 80, "internal_port": 80, "internal_ip": "192.168.68.10"},
+            {"id": "4", "protocol": "UDP", "external_port": "53", "internal_port": "53", "internal_ip": "192.168.68.10"}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(rules[0].id, "3");
+    assert_eq!(rules[1].id, "4");
+
+    let mappings: Vec<UpnpMapping> = serde_json::from_str(
+        r#"[
+            {"protocol": "UDP", "external_port": "3074", "internal_port": 3074, "internal_ip": "192.168.68.20"},
+            {"protocol": "TCP", "external_port": 8080, "internal_port": "80", "internal_ip": "192.168.68.21"}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(mappings[0].external_port, 3074);
+    assert_eq!(mappings[1].internal_port, 80);
+
+    let invalid = r#"{"protocol": "TCP", "external_port": 70000, "internal_port": 80, "internal_ip": "192.168.68.21"}"#;
+    assert!(serde_json::from_str::<UpnpMapping>(invalid).is_err());
+}
@@ -27,3 +27,91 @@ pub struct RebootResponseResult {
     pub reboot_time: i64,
 }
 pub type RebootResponse = MercusysAPIResponse<RebootResponseResult>;
+
+pub const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebootScheduleRequestBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<RebootSchedule>,
+
+    /// "read" or "write"
+    pub operation: String,
+}
+
+/// Built-in auto-reboot of the whole mesh.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebootSchedule {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Any of "mon", "tue"... "sun".
+    #[serde(default)]
+    pub days: Vec<String>,
+
+    /// Router local time, "HH:MM".
+    #[serde(default)]
+    pub time: String,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RebootSchedule {
+    /// Checked before anything gets written to the router.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enable {
+            return Ok(());
+        }
+
+        if self.days.is_empty() {
+            return Err("at least one day is needed".into());
+        }
+        if let Some(day) = self
+            .days
+            .iter()
+            .find(|day| !WEEKDAYS.contains(&day.as_str()))
+        {
+            return Err(format!("unknown day \"{}\"", day));
+        }
+        if chrono::NaiveTime::parse_from_str(&self.time, "%H:%M").is_err() {
+            return Err(format!("invalid time \"{}\", expected HH:MM", self.time));
+        }
+
+        Ok(())
+    }
+}
+pub type RebootScheduleResponse = MercusysAPIResponse<RebootSchedule>;
+
+#[test]
+fn reboot_schedule_validate_test() {
+    let mut schedule = RebootSchedule {
+        enable: true,
+        days: vec!["mon".into(), "thu".into()],
+        time: "03:30".into(),
+        ..Default::default()
+    };
+    assert!(schedule.validate().is_ok());
+
+    schedule.time = "3h30".into();
+    assert!(schedule.validate().is_err());
+
+    schedule.time = "03:30".into();
+    schedule.days.push("monday".into());
+    assert!(schedule.validate().is_err());
+
+    schedule.days.clear();
+    assert!(schedule.validate().is_err());
+    schedule.enable = false;
+    assert!(schedule.validate().is_ok());
+}
+
+#[test]
+fn reboot_schedule_roundtrip_test() {
+    let json = r#"{"enable":true,"days":["sun"],"time":"04:00","time_zone":"UTC+01:00"}"#;
+
+    let schedule: RebootSchedule = serde_json::from_str(json).unwrap();
+    assert!(schedule.extra.contains_key("time_zone"));
+    assert_eq!(serde_json::to_string(&schedule).unwrap(), json);
+}
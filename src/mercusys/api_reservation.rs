@@ -0,0 +1,85 @@
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::{decode_text, encode_text};
+use super::mac_address::MacAddress;
+
+pub type ReservationListResponse = MercusysAPIResponse<ReservationListResult>;
+pub type ReservationResponse = MercusysAPIResponse<ReservationListResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservationRequestBody {
+    /// "read", "add", "edit" or "remove"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<ReservationItem>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservationListResult {
+    #[serde(default)]
+    pub reservation_list: Vec<ReservationItem>,
+}
+
+/// A reservation as the router has it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReservationItem {
+    pub mac: MacAddress,
+    pub ip: Ipv4Addr,
+
+    /// Base64 encoded by the router.
+    #[serde(default)]
+    pub comment: String,
+}
+
+/// A DHCP address reservation, as kept in version control.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Reservation {
+    pub mac: MacAddress,
+    pub ip: Ipv4Addr,
+
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Parses its arguments, so tests can stick to plain strings.
+#[cfg(test)]
+pub fn make_test_reservation(mac: &str, ip: &str, description: &str) -> Reservation {
+    Reservation {
+        mac: mac.parse().unwrap(),
+        ip: ip.parse().unwrap(),
+        description: description.into(),
+    }
+}
+
+impl From<&ReservationItem> for Reservation {
+    fn from(item: &ReservationItem) -> Self {
+        Reservation {
+            mac: item.mac,
+            ip: item.ip,
+            description: decode_text(&item.comment),
+        }
+    }
+}
+
+impl From<&Reservation> for ReservationItem {
+    fn from(reservation: &Reservation) -> Self {
+        ReservationItem {
+            mac: reservation.mac,
+            ip: reservation.ip,
+            comment: encode_text(&reservation.description),
+        }
+    }
+}
+
+impl ReservationListResult {
+    pub fn reservations(&self) -> Vec<Reservation> {
+        self.reservation_list
+            .iter()
+            .map(Reservation::from)
+            .collect()
+    }
+}
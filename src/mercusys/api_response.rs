@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::error::{MercusysError, MercusysResult};
+
 fn default_true() -> bool {
     true
 }
@@ -17,3 +19,16 @@ pub struct MercusysAPIResponse<T> {
     #[serde(default = "default_true")]
     pub success: bool,
 }
+
+impl<T> MercusysAPIResponse<T> {
+    /// Turn a `success: false` response into an error.
+    pub fn check(self) -> MercusysResult<Self> {
+        if self.success {
+            Ok(self)
+        } else {
+            Err(MercusysError::Api {
+                error_code: self.error_code,
+            })
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type SpeedTestResponse = MercusysAPIResponse<SpeedTestResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedTestRequestBody {
+    /// "start" or "read"
+    pub operation: String,
+}
+
+/// Last (or running) speed test, run by the main unit against the ISP.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedTestResult {
+    /// "idle", "testing", "done" or "failed"
+    #[serde(default)]
+    pub status: String,
+
+    /// Kbps.
+    #[serde(default)]
+    pub download_speed: i64,
+
+    /// Kbps.
+    #[serde(default)]
+    pub upload_speed: i64,
+
+    /// Milliseconds.
+    #[serde(default)]
+    pub latency: i64,
+
+    /// Unix timestamp of the last completed test, 0 if never run.
+    #[serde(default)]
+    pub test_time: i64,
+}
+
+impl SpeedTestResult {
+    pub fn is_running(&self) -> bool {
+        self.status == "testing"
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == "done"
+    }
+
+    pub fn download_mbps(&self) -> f64 {
+        self.download_speed as f64 / 1000.0
+    }
+
+    pub fn upload_mbps(&self) -> f64 {
+        self.upload_speed as f64 / 1000.0
+    }
+}
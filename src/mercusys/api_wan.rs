@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+
+pub type WanStatusResponse = MercusysAPIResponse<WanStatus>;
+pub type WanReconnectResponse = MercusysAPIResponse<WanReconnectResult>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WanRequestBody {
+    /// "read", "renew" (DHCP) or "redial" (PPPoE)
+    pub operation: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WanStatus {
+    /// "dhcp", "static" or "pppoe"
+    #[serde(default)]
+    pub connection_type: String,
+
+    /// "connected", "connecting" or "disconnected"
+    #[serde(default)]
+    pub link_status: String,
+
+    /// Public (or upstream, behind another router) address.
+    #[serde(default, rename = "ipaddr")]
+    pub ip_address: String,
+    #[serde(default)]
+    pub netmask: String,
+    #[serde(default)]
+    pub gateway: String,
+    #[serde(default)]
+    pub dns1: String,
+    #[serde(default)]
+    pub dns2: String,
+
+    /// Seconds since the WAN came up.
+    #[serde(default)]
+    pub uptime: i64,
+
+    /// Ethernet link speed in Mbps, 0 if the cable is unplugged.
+    #[serde(default)]
+    pub link_speed: i64,
+    #[serde(default)]
+    pub link_duplex: String,
+}
+
+impl WanStatus {
+    pub fn is_connected(&self) -> bool {
+        self.link_status == "connected"
+    }
+
+    /// Operation to reconnect without rebooting, if the connection type has one.
+    pub fn reconnect_operation(&self) -> Option<&'static str> {
+        match self.connection_type.as_str() {
+            "dhcp" => Some("renew"),
+            "pppoe" => Some("redial"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WanReconnectResult {
+    /// Seconds the router expects it to take.
+    #[serde(default)]
+    pub wait_time: i64,
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+use super::api_response::MercusysAPIResponse;
+use super::encoding::{decode_text, encode_text};
+
+pub type WirelessResponse = MercusysAPIResponse<WirelessSettings>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WirelessRequestBody {
+    /// "read" or "write"
+    pub operation: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<WirelessSettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WirelessSettings {
+    #[serde(default)]
+    pub band2_4: WirelessBand,
+    #[serde(default)]
+    pub band5_1: WirelessBand,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WirelessBand {
+    /// The main (host) network, as opposed to the guest network.
+    #[serde(default)]
+    pub host: WirelessNetwork,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WirelessNetwork {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// base64 encoded
+    #[serde(default)]
+    pub ssid: String,
+
+    /// base64 encoded
+    #[serde(default)]
+    pub password: String,
+
+    /// "none", "psk" (WPA2), "psk_sae" (WPA2/WPA3) or "sae" (WPA3)
+    #[serde(default)]
+    pub encryption: String,
+
+    #[serde(default)]
+    pub hide_ssid: bool,
+
+    /// Fields we don't know about, sent back unchanged on write.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl WirelessNetwork {
+    pub fn ssid(&self) -> String {
+        decode_text(&self.ssid)
+    }
+
+    pub fn set_ssid(&mut self, ssid: &str) {
+        self.ssid = encode_text(ssid);
+    }
+
+    pub fn password(&self) -> String {
+        decode_text(&self.password)
+    }
+
+    pub fn set_password(&mut self, password: &str) {
+        self.password = encode_text(password);
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let ssid = self.ssid();
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err("SSID must be 1 to 32 bytes long".into());
+        }
+
+        match self.encryption.as_str() {
+            "none" => {}
+            "psk" | "psk_sae" | "sae" => {
+                let password = self.password();
+                let is_hex_key =
+                    password.len() == 64 && password.chars().all(|c| c.is_ascii_hexdigit());
+                let is_passphrase = (8..=63).contains(&password.len())
+                    && password
+                        .chars()
+                        .all(|c| c.is_ascii() && !c.is_ascii_control());
+
+                if !is_hex_key && !is_passphrase {
+                    return Err(
+                        "passphrase must be 8 to 63 printable ASCII characters (or 64 hex digits)"
+                            .into(),
+                    );
+                }
+            }
+            other => return Err(format!("unknown security mode \"{}\"", other)),
+        }
+
+        Ok(())
+    }
+}
+
+impl WirelessSettings {
+    pub fn bands(&self) -> [(&str, &WirelessNetwork); 2] {
+        [("2.4", &self.band2_4.host), ("5", &self.band5_1.host)]
+    }
+
+    /// Checked before anything gets written to the router.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.band2_4.host.enable && !self.band5_1.host.enable {
+            return Err("refusing to disable both bands".into());
+        }
+
+        for (band, network) in self.bands() {
+            network
+                .validate()
+                .map_err(|err| format!("{} GHz: {}", band, err))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn wireless_validate_test() {
+    let mut network = WirelessNetwork {
+        enable: true,
+        encryption: "psk".into(),
+        ..Default::default()
+    };
+    network.set_ssid("Home");
+    network.set_password("correct horse");
+    assert!(network.validate().is_ok());
+
+    network.set_password("short");
+    assert!(network.validate().is_err());
+
+    network.encryption = "none".into();
+    assert!(network.validate().is_ok());
+
+    network.set_ssid("");
+    assert!(network.validate().is_err());
+
+    let settings = WirelessSettings::default();
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn wireless_settings_roundtrip_test() {
+    let json = r#"{
+        "band2_4": {
+            "host": {"enable": true, "ssid": "SG9tZQ==", "password": "", "encryption": "psk", "hide_ssid": false, "channel": 6},
+            "tx_power": "high"
+        },
+        "band5_1": {"host": {"enable": true}},
+        "smart_connect": true
+    }"#;
+
+    let mut settings: WirelessSettings = serde_json::from_str(json).unwrap();
+    settings.band2_4.host.set_password("correct horse");
+
+    let written = serde_json::to_value(&settings).unwrap();
+    assert_eq!(written["smart_connect"], true);
+    assert_eq!(written["band2_4"]["tx_power"], "high");
+    assert_eq!(written["band2_4"]["host"]["channel"], 6);
+    assert_eq!(written["band2_4"]["host"]["ssid"], "SG9tZQ==");
+}
@@ -3,4 +3,4 @@ pub use rsa::rsa_encrypt;
 
 mod aes;
 mod payload;
-pub use payload::{PayloadManager, SignedPayload};
+pub use payload::PayloadManager;
@@ -57,7 +57,7 @@ impl PayloadManager {
         const AES_KEY_MIN: u64 = 1000_0000_0000_0000_u64;
         const AES_KEY_MAX: u64 = 5999_9999_9999_9998_u64;
 
-        if cfg!(feature = "no_rand") {
+        if cfg!(any(test, feature = "no_rand")) {
             self.aes_key = format!("{}", AES_KEY_MAX);
             self.aes_iv = format!("{}", AES_KEY_MAX);
         } else {
@@ -110,8 +110,7 @@ impl PayloadManager {
             format!("h={}&s={}", self.hash, data_len + self.seq)
         };
 
-        return r
-            .chars()
+        r.chars()
             .collect::<Vec<char>>()
             .chunks(53)
             .map(|chunk| {
@@ -121,14 +120,14 @@ impl PayloadManager {
                     &self.rsa_key_e,
                 )
             })
-            .collect::<String>();
+            .collect::<String>()
     }
 
     pub fn encrypt_payload(&self, payload: &str, is_login: bool) -> SignedPayload {
         let data = aes_128_cbc_encrypt(payload, &self.aes_key, &self.aes_iv);
         let data = general_purpose::STANDARD.encode(data);
 
-        let sign = self.sign(data.as_bytes().len() as i64, is_login);
+        let sign = self.sign(data.len() as i64, is_login);
 
         SignedPayload { data, sign }
     }
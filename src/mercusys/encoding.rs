@@ -0,0 +1,25 @@
+// The router base64-encodes user provided text (SSID, hostnames...),
+// presumably to dodge escaping issues in its web UI.
+
+use base64::{engine::general_purpose, Engine as _};
+
+/// Decode a base64 text field, or return it as is if it's not base64.
+pub fn decode_text(value: &str) -> String {
+    general_purpose::STANDARD
+        .decode(value)
+        .ok()
+        .and_then(|text| String::from_utf8(text).ok())
+        .filter(|text| !text.chars().any(char::is_control))
+        .unwrap_or_else(|| value.into())
+}
+
+pub fn encode_text(value: &str) -> String {
+    general_purpose::STANDARD.encode(value)
+}
+
+#[test]
+fn text_encoding_test() {
+    assert_eq!(encode_text("Home WiFi"), "SG9tZSBXaUZp");
+    assert_eq!(decode_text("SG9tZSBXaUZp"), "Home WiFi");
+    assert_eq!(decode_text("not base64!"), "not base64!");
+}
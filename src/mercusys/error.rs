@@ -0,0 +1,44 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MercusysError {
+    /// Transport level failure, e.g. router unreachable while rebooting.
+    Http(reqwest::Error),
+
+    /// Response could not be decrypted or parsed.
+    /// Usually means the session has expired.
+    Decrypt,
+
+    /// No session yet, login first.
+    NotLoggedIn,
+
+    /// Refused to send settings that did not pass validation.
+    Invalid(String),
+
+    /// Router responded, but with `success: false`.
+    Api { error_code: i64 },
+}
+
+pub type MercusysResult<T> = Result<T, MercusysError>;
+
+impl fmt::Display for MercusysError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MercusysError::Http(err) => write!(f, "http error: {}", err),
+            MercusysError::Decrypt => write!(f, "failed to decrypt response"),
+            MercusysError::NotLoggedIn => write!(f, "not logged in"),
+            MercusysError::Invalid(reason) => write!(f, "invalid settings: {}", reason),
+            MercusysError::Api { error_code } => {
+                write!(f, "router returned error code {}", error_code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MercusysError {}
+
+impl From<reqwest::Error> for MercusysError {
+    fn from(err: reqwest::Error) -> Self {
+        MercusysError::Http(err)
+    }
+}
@@ -1,15 +1,62 @@
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::{multipart, Client, Response};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::{cookie::Jar, Url};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::json;
 
-use super::api_device_list::DeviceListResponse;
+use super::api_access_control::{
+    AccessListItem, AccessListRequestBody, AccessListResponse, AccessListResult,
+    AccessModeRequestBody, AccessModeResponse, AccessModeSettings,
+};
+use super::api_account::{
+    validate_password, ChangePasswordParam, ChangePasswordRequestBody, ChangePasswordResponse,
+};
+use super::api_backup::{BackupErrorResponse, RestoreResponse};
+use super::api_client_list::ClientListResponse;
+use super::api_device_list::{
+    validate_nickname, DeviceListResponse, DeviceRemoveParam, DeviceRemoveRequestBody,
+    DeviceRemoveResponse, DeviceRequestBody, DeviceUpdateParam, DeviceUpdateResponse,
+    OnboardingRequestBody, OnboardingResponse,
+};
+use super::api_diagnostic::{
+    DiagnosticKind, DiagnosticParam, DiagnosticRequestBody, DiagnosticResponse,
+};
+use super::api_firmware::{
+    FirmwareCheckResponse, FirmwareMacAddress, FirmwareRequestBody, FirmwareRequestParam,
+    FirmwareStatusResponse, FirmwareUpgradeResponse,
+};
+use super::api_gateway::{SetGatewayParam, SetGatewayRequestBody, SetGatewayResponse};
+use super::api_guest::{GuestRequestBody, GuestResponse, GuestSettings};
+use super::api_led::{LedRequestBody, LedResponse, LedSettings};
+use super::api_log::{LogRequestBody, LogResponse};
 use super::api_login_keys::{AuthKeysResp, LoginKeysResp, LoginResp, MercusysEncryptedResponse};
 use super::api_logout::LogoutResponse;
-use super::api_reboot::{RebootMacAddress, RebootRequestBody, RebootResponse};
+use super::api_operation_mode::{
+    OperationModeParam, OperationModeRequestBody, OperationModeResponse, OPERATION_MODES,
+};
+use super::api_parental::{
+    ParentalProfile, ParentalProfileListResponse, ParentalProfileResponse, ParentalRequestBody,
+};
+use super::api_port_forwarding::{
+    PortForwardingListResponse, PortForwardingRemoveParam, PortForwardingRemoveRequestBody,
+    PortForwardingRequestBody, PortForwardingRule, UpnpListResponse, UpnpRequestBody, UpnpSettings,
+    UpnpSettingsResponse,
+};
+use super::api_reboot::{
+    RebootMacAddress, RebootRequestBody, RebootResponse, RebootSchedule, RebootScheduleRequestBody,
+    RebootScheduleResponse,
+};
+use super::api_reservation::{
+    Reservation, ReservationListResponse, ReservationRequestBody, ReservationResponse,
+};
+use super::api_speedtest::{SpeedTestRequestBody, SpeedTestResponse};
+use super::api_wan::{WanReconnectResponse, WanRequestBody, WanStatusResponse};
+use super::api_wireless::{WirelessRequestBody, WirelessResponse, WirelessSettings};
 use super::crypto::{rsa_encrypt, PayloadManager};
+use super::encoding::encode_text;
+use super::error::{MercusysError, MercusysResult};
+use super::mac_address::MacAddress;
 
 pub struct MercusysHTTP {
     logging_enabled: bool,
@@ -18,6 +65,9 @@ pub struct MercusysHTTP {
     base_url: Url,
     pub stok: String,
     pub session: PayloadManager,
+
+    /// Kept around so long running modes can login again.
+    credentials: Option<(String, String)>,
 }
 
 impl MercusysHTTP {
@@ -45,6 +95,7 @@ impl MercusysHTTP {
             client: builder.build().unwrap(),
             stok: "".into(),
             session: PayloadManager::new(),
+            credentials: None,
         }
     }
 
@@ -53,15 +104,36 @@ impl MercusysHTTP {
         self.session.set_logging_enabled(enabled);
     }
 
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Talk to the router at another address, e.g. after an operation mode
+    ///   switch. The session is dropped, `with_relogin` logs in again.
+    pub fn set_base_url(&mut self, base_url: Url) {
+        let credentials = self.credentials.take();
+        let logging_enabled = self.logging_enabled;
+
+        *self = MercusysHTTP::new(base_url);
+        self.set_logging_enabled(logging_enabled);
+        self.credentials = credentials;
+    }
+
+    /// Endpoint URL within the current session.
+    fn url(&self, path: &str, form: &str) -> Url {
+        let path = format!("/cgi-bin/luci/;stok={}{}", self.stok, path);
+        let mut url = self.base_url.join(path.as_str()).unwrap();
+        url.query_pairs_mut().append_pair("form", form);
+        url
+    }
+
     pub fn req<T: Serialize + ?Sized>(
         &mut self,
         path: &str,
         form: &str,
         data: &T,
     ) -> Result<Response, reqwest::Error> {
-        let path = format!("/cgi-bin/luci/;stok={}{}", self.stok, path);
-        let mut url = self.base_url.join(path.as_str()).unwrap();
-        url.query_pairs_mut().append_pair("form", form);
+        let url = self.url(path, form);
         self.client.post(url).json(&data).send()
     }
 
@@ -70,10 +142,8 @@ impl MercusysHTTP {
         path: &str,
         form: &str,
         data: &T,
-    ) -> Result<R, reqwest::Error> {
-        let path = format!("/cgi-bin/luci/;stok={}{}", self.stok, path);
-        let mut url = self.base_url.join(path.as_str()).unwrap();
-        url.query_pairs_mut().append_pair("form", form);
+    ) -> MercusysResult<R> {
+        let url = self.url(path, form);
 
         if self.logging_enabled {
             eprintln!("req data: {}", serde_json::to_string(&data).unwrap());
@@ -82,6 +152,9 @@ impl MercusysHTTP {
         // There should be a better way to check if we are logging in,
         //   but it works... so whatever...
         let is_login_request = form == "login";
+        if !is_login_request && self.stok.is_empty() {
+            return Err(MercusysError::NotLoggedIn);
+        }
 
         let response = self
             .client
@@ -101,14 +174,15 @@ impl MercusysHTTP {
 
             self.session
                 .decrypt_response::<R>(response_data.data.as_str())
-                .unwrap() // FIXME: handle error gracefully
+                .ok_or(MercusysError::Decrypt)?
         };
 
         Ok(data)
     }
 
     /// Use "admin" for username if unsure.
-    pub fn login(&mut self, username: &str, password: &str) {
+    pub fn login(&mut self, username: &str, password: &str) -> MercusysResult<()> {
+        self.credentials = Some((username.into(), password.into()));
         self.session = PayloadManager::new();
         self.session.set_logging_enabled(self.logging_enabled);
         self.session.gen_aes_key();
@@ -116,10 +190,8 @@ impl MercusysHTTP {
 
         let encrypted_password = {
             let password_key_resp = self
-                .req("/login", "keys", &json!({"operation":"read"}))
-                .unwrap()
-                .json::<LoginKeysResp>()
-                .unwrap();
+                .req("/login", "keys", &json!({"operation":"read"}))?
+                .json::<LoginKeysResp>()?;
 
             let rsa_n = password_key_resp.result.password[0].as_str();
             let rsa_e = password_key_resp.result.password[1].as_str();
@@ -128,10 +200,8 @@ impl MercusysHTTP {
 
         {
             let auth_key_resp = self
-                .req("/login", "auth", &json!({"operation":"read"}))
-                .unwrap()
-                .json::<AuthKeysResp>()
-                .unwrap();
+                .req("/login", "auth", &json!({"operation":"read"}))?
+                .json::<AuthKeysResp>()?;
 
             self.session.set_seq(auth_key_resp.result.seq);
 
@@ -141,35 +211,720 @@ impl MercusysHTTP {
         }
 
         // Now we can login...
-        let login_result: LoginResp = self
-            .req_encrypted(
-                "/login",
-                "login",
-                &json!({
-                    "params":{
-                        "password": encrypted_password
-                    },
-                    "operation":"login"
-                }),
-            )
-            .unwrap();
-        self.stok = login_result.result.stok;
+        let login_result: LoginResp = self.req_encrypted(
+            "/login",
+            "login",
+            &json!({
+                "params":{
+                    "password": encrypted_password
+                },
+                "operation":"login"
+            }),
+        )?;
+        self.stok = login_result.check()?.result.stok;
+
+        Ok(())
+    }
+
+    /// Change the admin password. The new one is validated first. The router
+    ///   ends the session, login again with the new password afterwards.
+    pub fn change_password(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+    ) -> MercusysResult<ChangePasswordResponse> {
+        validate_password(new_password).map_err(MercusysError::Invalid)?;
+
+        let keys = self
+            .req("/login", "keys", &json!({"operation":"read"}))?
+            .json::<LoginKeysResp>()?;
+        let rsa_n = keys.result.password[0].as_str();
+        let rsa_e = keys.result.password[1].as_str();
+
+        let body = ChangePasswordRequestBody {
+            operation: "write".into(),
+            params: ChangePasswordParam {
+                old_password: rsa_encrypt(old_password, rsa_n, rsa_e),
+                new_password: rsa_encrypt(new_password, rsa_n, rsa_e),
+            },
+        };
+        let resp: ChangePasswordResponse =
+            self.req_encrypted("/admin/administration", "account", &body)?;
+        let resp = resp.check()?;
+
+        if let Some((_, password)) = self.credentials.as_mut() {
+            *password = new_password.into();
+        }
+        Ok(resp)
     }
 
-    pub fn get_device_list(&mut self) -> DeviceListResponse {
-        self.req_encrypted("/admin/device", "device_list", &json!({"operation":"read"}))
-            .unwrap()
+    /// Check the session with a read, logging in again if it expired. Use it
+    ///   before requests that must not be retried, such as reboots.
+    pub fn ensure_session(&mut self) -> MercusysResult<()> {
+        self.with_relogin(|api| api.get_device_list()).map(|_| ())
     }
 
-    pub fn reboot_whole_mesh(&mut self) -> RebootResponse {
-        let devices = self.get_device_list();
+    /// Run `f`, and if it fails (session expired, router rebooted...),
+    ///   login again and retry once.
+    ///   Only for idempotent requests, `f` may run twice.
+    pub fn with_relogin<R, F>(&mut self, mut f: F) -> MercusysResult<R>
+    where
+        F: FnMut(&mut MercusysHTTP) -> MercusysResult<R>,
+    {
+        let err = match f(self) {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
 
-        let macs: Vec<RebootMacAddress> = devices
-            .result
-            .device_list
+        let (username, password) = match self.credentials.clone() {
+            Some(credentials) => credentials,
+            None => return Err(err),
+        };
+
+        if self.logging_enabled {
+            eprintln!("request failed ({}), login again...", err);
+        }
+        self.login(&username, &password)?;
+        f(self)
+    }
+
+    pub fn get_device_list(&mut self) -> MercusysResult<DeviceListResponse> {
+        let resp: DeviceListResponse =
+            self.req_encrypted("/admin/device", "device_list", &json!({"operation":"read"}))?;
+        resp.check()
+    }
+
+    /// Set the nickname (location) of a node. It is validated first.
+    pub fn set_device_nickname(
+        &mut self,
+        mac: MacAddress,
+        nickname: &str,
+    ) -> MercusysResult<DeviceUpdateResponse> {
+        validate_nickname(nickname).map_err(MercusysError::Invalid)?;
+        let params = DeviceUpdateParam {
+            mac: mac.to_string(),
+            nickname: encode_text(nickname),
+        };
+
+        let body = DeviceRequestBody {
+            operation: "write".into(),
+            params,
+        };
+        let resp: DeviceUpdateResponse = self.req_encrypted("/admin/device", "device", &body)?;
+        resp.check()
+    }
+
+    /// Promote a node to main unit. Refused unless the node is connected to
+    ///   the mesh and reports `set_gateway_support`.
+    pub fn set_gateway(&mut self, mac: MacAddress) -> MercusysResult<SetGatewayResponse> {
+        let devices = self.get_device_list()?.result.device_list;
+        let device = devices
+            .iter()
+            .find(|device| mac.matches(&device.mac))
+            .ok_or_else(|| MercusysError::Invalid(format!("no node with MAC {}", mac)))?;
+
+        if device.is_main() {
+            return Err(MercusysError::Invalid(format!(
+                "{} already is the main unit",
+                mac
+            )));
+        }
+        if !device.set_gateway_support {
+            return Err(MercusysError::Invalid(format!(
+                "{} can't be the main unit",
+                mac
+            )));
+        }
+        if !device.is_connected() {
+            return Err(MercusysError::Invalid(format!(
+                "{} is not connected to the mesh",
+                mac
+            )));
+        }
+
+        let body = SetGatewayRequestBody {
+            operation: "write".into(),
+            params: SetGatewayParam {
+                mac: device.mac.clone(),
+            },
+        };
+        let resp: SetGatewayResponse = self.req_encrypted("/admin/device", "set_gateway", &body)?;
+        resp.check()
+    }
+
+    /// Remove a satellite from the mesh, e.g. a dead one being replaced.
+    ///   The main unit can't be removed.
+    pub fn remove_device(&mut self, mac: MacAddress) -> MercusysResult<DeviceRemoveResponse> {
+        let devices = self.get_device_list()?.result.device_list;
+        let device = devices
             .iter()
-            .map(|device| RebootMacAddress {
+            .find(|device| mac.matches(&device.mac))
+            .ok_or_else(|| MercusysError::Invalid(format!("no node with MAC {}", mac)))?;
+        if device.is_main() {
+            return Err(MercusysError::Invalid(
+                "the main unit can't be removed".into(),
+            ));
+        }
+
+        let body = DeviceRemoveRequestBody {
+            operation: "remove".into(),
+            params: DeviceRemoveParam {
                 mac: device.mac.clone(),
+            },
+        };
+        let resp: DeviceRemoveResponse =
+            self.req_encrypted("/admin/device", "device_list", &body)?;
+        resp.check()
+    }
+
+    /// Start or stop looking for new satellites.
+    pub fn set_onboarding(&mut self, start: bool) -> MercusysResult<OnboardingResponse> {
+        let body = OnboardingRequestBody {
+            operation: if start { "start" } else { "stop" }.into(),
+        };
+        let resp: OnboardingResponse = self.req_encrypted("/admin/device", "onboarding", &body)?;
+        resp.check()
+    }
+
+    /// Clients (phones, laptops...) attached to any node of the mesh.
+    pub fn get_client_list(&mut self) -> MercusysResult<ClientListResponse> {
+        let resp: ClientListResponse = self.req_encrypted(
+            "/admin/client",
+            "client_list",
+            &json!({"operation":"read", "params": {"device_mac": "default"}}),
+        )?;
+        resp.check()
+    }
+
+    pub fn get_wireless_settings(&mut self) -> MercusysResult<WirelessResponse> {
+        let body = WirelessRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: WirelessResponse = self.req_encrypted("/admin/wireless", "wlan", &body)?;
+        resp.check()
+    }
+
+    /// Settings are validated first, nothing is sent if they are invalid.
+    pub fn set_wireless_settings(
+        &mut self,
+        settings: &WirelessSettings,
+    ) -> MercusysResult<WirelessResponse> {
+        settings.validate().map_err(MercusysError::Invalid)?;
+
+        let body = WirelessRequestBody {
+            operation: "write".into(),
+            params: Some(settings.clone()),
+        };
+        let resp: WirelessResponse = self.req_encrypted("/admin/wireless", "wlan", &body)?;
+        resp.check()
+    }
+
+    pub fn get_guest_settings(&mut self) -> MercusysResult<GuestResponse> {
+        let body = GuestRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: GuestResponse = self.req_encrypted("/admin/wireless", "guest", &body)?;
+        resp.check()
+    }
+
+    /// Settings are validated first, nothing is sent if they are invalid.
+    pub fn set_guest_settings(
+        &mut self,
+        settings: &GuestSettings,
+    ) -> MercusysResult<GuestResponse> {
+        settings.validate().map_err(MercusysError::Invalid)?;
+
+        let body = GuestRequestBody {
+            operation: "write".into(),
+            params: Some(settings.clone()),
+        };
+        let resp: GuestResponse = self.req_encrypted("/admin/wireless", "guest", &body)?;
+        resp.check()
+    }
+
+    /// Turn the guest network on or off, keeping its other settings.
+    pub fn set_guest_enabled(&mut self, enable: bool) -> MercusysResult<GuestResponse> {
+        let mut settings = self.get_guest_settings()?.result;
+        settings.network.enable = enable;
+        self.set_guest_settings(&settings)
+    }
+
+    pub fn get_access_mode(&mut self) -> MercusysResult<AccessModeResponse> {
+        let body = AccessModeRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: AccessModeResponse =
+            self.req_encrypted("/admin/access_control", "mode", &body)?;
+        resp.check()
+    }
+
+    pub fn set_access_mode(
+        &mut self,
+        settings: &AccessModeSettings,
+    ) -> MercusysResult<AccessModeResponse> {
+        let body = AccessModeRequestBody {
+            operation: "write".into(),
+            params: Some(settings.clone()),
+        };
+        let resp: AccessModeResponse =
+            self.req_encrypted("/admin/access_control", "mode", &body)?;
+        resp.check()
+    }
+
+    fn access_list_req(
+        &mut self,
+        form: &str,
+        operation: &str,
+        client_list: Option<Vec<AccessListItem>>,
+    ) -> MercusysResult<AccessListResponse> {
+        let body = AccessListRequestBody {
+            operation: operation.into(),
+            params: client_list.map(|client_list| AccessListResult { client_list }),
+        };
+        let resp: AccessListResponse = self.req_encrypted("/admin/access_control", form, &body)?;
+        resp.check()
+    }
+
+    /// Clients on the list used by the current mode (blocked or allowed ones).
+    pub fn get_access_list(
+        &mut self,
+        mode: &AccessModeSettings,
+    ) -> MercusysResult<AccessListResponse> {
+        self.access_list_req(mode.list_form(), "read", None)
+    }
+
+    /// Block or unblock a client, whatever the access control mode.
+    ///   Returns false if there was nothing to change.
+    fn set_client_access(
+        &mut self,
+        mac: MacAddress,
+        name: &str,
+        allowed: bool,
+    ) -> MercusysResult<bool> {
+        let mut mode = self.get_access_mode()?.result;
+        if !mode.enable {
+            if allowed {
+                return Ok(false);
+            }
+            if mode.is_allowlist() {
+                // Enabling it would cut off every client not on the list.
+                return Err(MercusysError::Invalid(
+                    "access control is disabled and set to allowlist mode".into(),
+                ));
+            }
+            mode.enable = true;
+            self.set_access_mode(&mode)?;
+        }
+
+        let listed = self
+            .get_access_list(&mode)?
+            .result
+            .client_list
+            .iter()
+            .any(|item| item.mac == mac);
+
+        let want_listed = mode.wants_listed(allowed);
+        if listed == want_listed {
+            return Ok(false);
+        }
+
+        let operation = if want_listed { "add" } else { "remove" };
+        let item = AccessListItem {
+            mac,
+            name: encode_text(name),
+        };
+        self.access_list_req(mode.list_form(), operation, Some(vec![item]))?;
+        Ok(true)
+    }
+
+    /// Cut a client off. Returns false if it already was.
+    pub fn block_client(&mut self, mac: MacAddress, name: &str) -> MercusysResult<bool> {
+        self.set_client_access(mac, name, false)
+    }
+
+    /// Give a client access again. Returns false if it already had it.
+    pub fn unblock_client(&mut self, mac: MacAddress, name: &str) -> MercusysResult<bool> {
+        self.set_client_access(mac, name, true)
+    }
+
+    pub fn get_parental_profiles(&mut self) -> MercusysResult<ParentalProfileListResponse> {
+        let body = ParentalRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: ParentalProfileListResponse =
+            self.req_encrypted("/admin/parental_control", "profile_list", &body)?;
+        resp.check()
+    }
+
+    /// Update an existing profile, matched by `profile_id`.
+    pub fn set_parental_profile(
+        &mut self,
+        profile: &ParentalProfile,
+    ) -> MercusysResult<ParentalProfileResponse> {
+        let body = ParentalRequestBody {
+            operation: "write".into(),
+            params: Some(profile.clone()),
+        };
+        let resp: ParentalProfileResponse =
+            self.req_encrypted("/admin/parental_control", "profile", &body)?;
+        resp.check()
+    }
+
+    /// Pause or resume internet access for everyone in a profile.
+    pub fn set_profile_paused(
+        &mut self,
+        profile: &ParentalProfile,
+        paused: bool,
+    ) -> MercusysResult<ParentalProfileResponse> {
+        let profile = ParentalProfile {
+            internet_paused: paused,
+            ..profile.clone()
+        };
+        self.set_parental_profile(&profile)
+    }
+
+    pub fn get_wan_status(&mut self) -> MercusysResult<WanStatusResponse> {
+        let body = WanRequestBody {
+            operation: "read".into(),
+        };
+        let resp: WanStatusResponse = self.req_encrypted("/admin/network", "wan_status", &body)?;
+        resp.check()
+    }
+
+    /// Renew the DHCP lease or redial PPPoE, without rebooting the mesh.
+    pub fn reconnect_wan(&mut self) -> MercusysResult<WanReconnectResponse> {
+        let status = self.get_wan_status()?.result;
+        let operation = status.reconnect_operation().ok_or_else(|| {
+            MercusysError::Invalid(format!(
+                "can't reconnect a \"{}\" WAN connection",
+                status.connection_type
+            ))
+        })?;
+
+        let body = WanRequestBody {
+            operation: operation.into(),
+        };
+        let resp: WanReconnectResponse =
+            self.req_encrypted("/admin/network", "wan_status", &body)?;
+        resp.check()
+    }
+
+    /// DHCP address reservations.
+    pub fn get_reservations(&mut self) -> MercusysResult<ReservationListResponse> {
+        let body = ReservationRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: ReservationListResponse =
+            self.req_encrypted("/admin/dhcps", "reservation", &body)?;
+        resp.check()
+    }
+
+    fn reservation_req(
+        &mut self,
+        operation: &str,
+        reservation: &Reservation,
+    ) -> MercusysResult<ReservationResponse> {
+        let body = ReservationRequestBody {
+            operation: operation.into(),
+            params: Some(reservation.into()),
+        };
+        let resp: ReservationResponse = self.req_encrypted("/admin/dhcps", "reservation", &body)?;
+        resp.check()
+    }
+
+    pub fn add_reservation(
+        &mut self,
+        reservation: &Reservation,
+    ) -> MercusysResult<ReservationResponse> {
+        self.reservation_req("add", reservation)
+    }
+
+    /// Change the IP or description of the reservation for the same MAC.
+    pub fn update_reservation(
+        &mut self,
+        reservation: &Reservation,
+    ) -> MercusysResult<ReservationResponse> {
+        self.reservation_req("edit", reservation)
+    }
+
+    pub fn remove_reservation(
+        &mut self,
+        reservation: &Reservation,
+    ) -> MercusysResult<ReservationResponse> {
+        self.reservation_req("remove", reservation)
+    }
+
+    pub fn get_port_forwarding_rules(&mut self) -> MercusysResult<PortForwardingListResponse> {
+        let body = PortForwardingRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: PortForwardingListResponse = self.req_encrypted("/admin/nat", "vs", &body)?;
+        resp.check()
+    }
+
+    /// Fails if the rule is invalid, or its ports are already taken by another
+    ///   rule or a UPnP mapping.
+    pub fn check_port_forwarding_rule(&mut self, rule: &PortForwardingRule) -> MercusysResult<()> {
+        rule.validate().map_err(MercusysError::Invalid)?;
+
+        let rules = self.get_port_forwarding_rules()?.result.rule_list;
+        if let Some(other) = rules
+            .iter()
+            .find(|other| other.conflicts_with(rule.protocol, &rule.external_port))
+        {
+            // Disabled rules count too, they would clash once enabled again.
+            return Err(MercusysError::Invalid(format!(
+                "{} {} conflicts with {}rule {} ({} {} -> {})",
+                rule.protocol,
+                rule.external_port,
+                if other.enable { "" } else { "disabled " },
+                other.id,
+                other.protocol,
+                other.external_port,
+                other.internal_ip
+            )));
+        }
+
+        if self.get_upnp_settings()?.result.enable {
+            let mappings = self.get_upnp_mappings()?.result.upnp_list;
+            if let Some(mapping) = mappings.iter().find(|mapping| {
+                mapping.protocol.overlaps(rule.protocol)
+                    && rule.external_port.overlaps(&mapping.external_port.into())
+            }) {
+                return Err(MercusysError::Invalid(format!(
+                    "{} {} conflicts with the UPnP mapping of {} ({} {})",
+                    rule.protocol,
+                    rule.external_port,
+                    mapping.internal_ip,
+                    mapping.protocol,
+                    mapping.external_port
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn add_port_forwarding_rule(
+        &mut self,
+        rule: &PortForwardingRule,
+    ) -> MercusysResult<PortForwardingListResponse> {
+        self.check_port_forwarding_rule(rule)?;
+
+        let body = PortForwardingRequestBody {
+            operation: "add".into(),
+            params: Some(rule.clone()),
+        };
+        let resp: PortForwardingListResponse = self.req_encrypted("/admin/nat", "vs", &body)?;
+        resp.check()
+    }
+
+    pub fn remove_port_forwarding_rule(
+        &mut self,
+        id: &str,
+    ) -> MercusysResult<PortForwardingListResponse> {
+        let body = PortForwardingRemoveRequestBody {
+            operation: "remove".into(),
+            params: PortForwardingRemoveParam { id: id.into() },
+        };
+        let resp: PortForwardingListResponse = self.req_encrypted("/admin/nat", "vs", &body)?;
+        resp.check()
+    }
+
+    pub fn get_upnp_settings(&mut self) -> MercusysResult<UpnpSettingsResponse> {
+        let body = UpnpRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: UpnpSettingsResponse = self.req_encrypted("/admin/nat", "upnp_setting", &body)?;
+        resp.check()
+    }
+
+    pub fn set_upnp(&mut self, enable: bool) -> MercusysResult<UpnpSettingsResponse> {
+        let body = UpnpRequestBody {
+            operation: "write".into(),
+            params: Some(UpnpSettings { enable }),
+        };
+        let resp: UpnpSettingsResponse = self.req_encrypted("/admin/nat", "upnp_setting", &body)?;
+        resp.check()
+    }
+
+    /// Mappings currently opened by devices through UPnP.
+    pub fn get_upnp_mappings(&mut self) -> MercusysResult<UpnpListResponse> {
+        let body = UpnpRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: UpnpListResponse = self.req_encrypted("/admin/nat", "upnp_list", &body)?;
+        resp.check()
+    }
+
+    /// Start a ping or traceroute from the main unit, poll `get_diagnostic` for the output.
+    pub fn start_diagnostic(
+        &mut self,
+        kind: DiagnosticKind,
+        params: DiagnosticParam,
+    ) -> MercusysResult<DiagnosticResponse> {
+        params.validate().map_err(MercusysError::Invalid)?;
+
+        let body = DiagnosticRequestBody {
+            operation: "start".into(),
+            params: Some(params),
+        };
+        let resp: DiagnosticResponse =
+            self.req_encrypted("/admin/diagnostic", kind.form(), &body)?;
+        resp.check()
+    }
+
+    pub fn get_diagnostic(&mut self, kind: DiagnosticKind) -> MercusysResult<DiagnosticResponse> {
+        let body = DiagnosticRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: DiagnosticResponse =
+            self.req_encrypted("/admin/diagnostic", kind.form(), &body)?;
+        resp.check()
+    }
+
+    /// Start a speed test in the background, poll `get_speed_test` for the result.
+    pub fn start_speed_test(&mut self) -> MercusysResult<SpeedTestResponse> {
+        let body = SpeedTestRequestBody {
+            operation: "start".into(),
+        };
+        let resp: SpeedTestResponse = self.req_encrypted("/admin/network", "speedtest", &body)?;
+        resp.check()
+    }
+
+    pub fn get_speed_test(&mut self) -> MercusysResult<SpeedTestResponse> {
+        let body = SpeedTestRequestBody {
+            operation: "read".into(),
+        };
+        let resp: SpeedTestResponse = self.req_encrypted("/admin/network", "speedtest", &body)?;
+        resp.check()
+    }
+
+    /// Ask the router to look for newer firmware, for every node.
+    pub fn check_online_firmware(&mut self) -> MercusysResult<FirmwareCheckResponse> {
+        let body = FirmwareRequestBody {
+            operation: "check".into(),
+            params: None,
+        };
+        let resp: FirmwareCheckResponse =
+            self.req_encrypted("/admin/firmware", "upgrade", &body)?;
+        resp.check()
+    }
+
+    /// Download and install the online firmware on the given nodes.
+    /// They reboot on their own once done.
+    pub fn upgrade_firmware<S: AsRef<str>>(
+        &mut self,
+        macs: &[S],
+    ) -> MercusysResult<FirmwareUpgradeResponse> {
+        let macs: Vec<FirmwareMacAddress> = macs
+            .iter()
+            .map(|mac| FirmwareMacAddress {
+                mac: mac.as_ref().into(),
+            })
+            .collect();
+        let body = FirmwareRequestBody {
+            operation: "upgrade".into(),
+            params: Some(FirmwareRequestParam {
+                mac_address_list: macs,
+            }),
+        };
+
+        let resp: FirmwareUpgradeResponse =
+            self.req_encrypted("/admin/firmware", "upgrade", &body)?;
+        resp.check()
+    }
+
+    pub fn get_firmware_upgrade_status(&mut self) -> MercusysResult<FirmwareStatusResponse> {
+        let body = FirmwareRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: FirmwareStatusResponse =
+            self.req_encrypted("/admin/firmware", "upgrade_status", &body)?;
+        resp.check()
+    }
+
+    /// Download the router configuration, as the opaque (encrypted) blob
+    ///   the web UI would save.
+    pub fn download_config_backup(&mut self) -> MercusysResult<Vec<u8>> {
+        if self.stok.is_empty() {
+            return Err(MercusysError::NotLoggedIn);
+        }
+
+        let url = self.url("/admin/firmware", "config_backup");
+        let response = self
+            .client
+            .post(url)
+            .form(&[("operation", "backup")])
+            .send()?
+            .error_for_status()?;
+        let data = response.bytes()?.to_vec();
+
+        // An expired session still gets a 200, with an error response as body.
+        if let Ok(resp) = serde_json::from_slice::<BackupErrorResponse>(&data) {
+            return Err(MercusysError::Api {
+                error_code: resp.error_code,
+            });
+        }
+
+        Ok(data)
+    }
+
+    /// Upload a configuration backup. The router reboots afterwards.
+    pub fn restore_config_backup(&mut self, backup: Vec<u8>) -> MercusysResult<RestoreResponse> {
+        if self.stok.is_empty() {
+            return Err(MercusysError::NotLoggedIn);
+        }
+
+        let url = self.url("/admin/firmware", "config_restore");
+        let part = multipart::Part::bytes(backup).file_name("backup.bin");
+        let form = multipart::Form::new()
+            .text("operation", "restore")
+            .part("image", part);
+
+        let resp: RestoreResponse = self
+            .client
+            .post(url)
+            .multipart(form)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        resp.check()
+    }
+
+    /// System log of the main unit, oldest entries first.
+    pub fn get_system_log(&mut self) -> MercusysResult<LogResponse> {
+        let body = LogRequestBody {
+            operation: "read".into(),
+        };
+        let resp: LogResponse = self.req_encrypted("/admin/system", "log", &body)?;
+        resp.check()
+    }
+
+    pub fn clear_system_log(&mut self) -> MercusysResult<LogResponse> {
+        let body = LogRequestBody {
+            operation: "clear".into(),
+        };
+        let resp: LogResponse = self.req_encrypted("/admin/system", "log", &body)?;
+        resp.check()
+    }
+
+    /// Reboot only the given nodes, identified by their MAC address.
+    pub fn reboot_devices<S: AsRef<str>>(&mut self, macs: &[S]) -> MercusysResult<RebootResponse> {
+        let macs: Vec<RebootMacAddress> = macs
+            .iter()
+            .map(|mac| RebootMacAddress {
+                mac: mac.as_ref().into(),
             })
             .collect();
         let body = RebootRequestBody {
@@ -179,21 +934,237 @@ impl MercusysHTTP {
             operation: "reboot".into(),
         };
 
-        self.req_encrypted("/admin/device", "system", &body)
-            .unwrap()
+        let resp: RebootResponse = self.req_encrypted("/admin/device", "system", &body)?;
+        resp.check()
     }
 
-    pub fn logout(&mut self) -> bool {
-        let resp: LogoutResponse = self
-            .req_encrypted("/admin/system", "logout", &json!({"operation":"logout"}))
-            .unwrap();
+    pub fn reboot_whole_mesh(&mut self) -> MercusysResult<RebootResponse> {
+        let devices = self.get_device_list()?;
+
+        let macs: Vec<&str> = devices
+            .result
+            .device_list
+            .iter()
+            .map(|device| device.mac.as_str())
+            .collect();
+
+        self.reboot_devices(&macs)
+    }
+
+    pub fn get_reboot_schedule(&mut self) -> MercusysResult<RebootScheduleResponse> {
+        let body = RebootScheduleRequestBody {
+            params: None,
+            operation: "read".into(),
+        };
+        let resp: RebootScheduleResponse =
+            self.req_encrypted("/admin/device", "reboot_schedule", &body)?;
+        resp.check()
+    }
+
+    /// Schedule is validated first, nothing is sent if it is invalid.
+    pub fn set_reboot_schedule(
+        &mut self,
+        schedule: &RebootSchedule,
+    ) -> MercusysResult<RebootScheduleResponse> {
+        schedule.validate().map_err(MercusysError::Invalid)?;
+
+        let body = RebootScheduleRequestBody {
+            params: Some(schedule.clone()),
+            operation: "write".into(),
+        };
+        let resp: RebootScheduleResponse =
+            self.req_encrypted("/admin/device", "reboot_schedule", &body)?;
+        resp.check()
+    }
+
+    pub fn get_led_settings(&mut self) -> MercusysResult<LedResponse> {
+        let body = LedRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: LedResponse = self.req_encrypted("/admin/ledgeneral", "setting", &body)?;
+        resp.check()
+    }
+
+    /// Settings are validated first, nothing is sent if they are invalid.
+    pub fn set_led_settings(&mut self, settings: &LedSettings) -> MercusysResult<LedResponse> {
+        settings.validate().map_err(MercusysError::Invalid)?;
+
+        let body = LedRequestBody {
+            operation: "write".into(),
+            params: Some(settings.clone()),
+        };
+        let resp: LedResponse = self.req_encrypted("/admin/ledgeneral", "setting", &body)?;
+        resp.check()
+    }
+
+    pub fn get_operation_mode(&mut self) -> MercusysResult<OperationModeResponse> {
+        let body = OperationModeRequestBody {
+            operation: "read".into(),
+            params: None,
+        };
+        let resp: OperationModeResponse = self.req_encrypted("/admin/system", "sysmode", &body)?;
+        resp.check()
+    }
+
+    /// Switch between "router" and "ap". The whole mesh restarts, and may
+    ///   come back at another address.
+    pub fn set_operation_mode(&mut self, mode: &str) -> MercusysResult<OperationModeResponse> {
+        if !OPERATION_MODES.contains(&mode) {
+            return Err(MercusysError::Invalid(format!(
+                "unknown operation mode \"{}\"",
+                mode
+            )));
+        }
+
+        let body = OperationModeRequestBody {
+            operation: "write".into(),
+            params: Some(OperationModeParam { mode: mode.into() }),
+        };
+        let resp: OperationModeResponse = self.req_encrypted("/admin/system", "sysmode", &body)?;
+        resp.check()
+    }
+
+    pub fn logout(&mut self) -> MercusysResult<bool> {
+        let resp: LogoutResponse =
+            self.req_encrypted("/admin/system", "logout", &json!({"operation":"logout"}))?;
 
         if resp.success {
             self.stok = "".into();
+            self.credentials = None;
             self.session = PayloadManager::new();
             self.session.set_logging_enabled(self.logging_enabled);
         }
 
-        resp.success
+        Ok(resp.success)
     }
 }
+
+#[cfg(test)]
+const FAKE_ROUTER_RSA_N: &str = "C4E3F7212602E1E396C0B6623CF11D26204ACE3E7D26685E037AD2507DCE82FC28F2D5F8A67FC3AFAB89A6D818D1F4C28CFA548418BD9F8E7426789A67E73E41";
+
+/// Router on localhost, just enough of it to login. Every other request goes
+///   to `handle(form, request)`, and the returned response is sent back
+///   encrypted. Only the stok of the last login is accepted, set `api.stok`
+///   to anything else to let the session expire.
+#[cfg(test)]
+pub fn spawn_fake_router<F>(mut handle: F) -> Url
+where
+    F: FnMut(&str, &serde_json::Value) -> serde_json::Value + Send + 'static,
+{
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!(
+        "http://{}/",
+        server.server_addr().to_ip().unwrap()
+    ))
+    .unwrap();
+
+    // Test builds use a fixed AES key, the same one the client picks.
+    let mut session = PayloadManager::new();
+    session.gen_aes_key();
+    session.set_rsa_key(FAKE_ROUTER_RSA_N, "010001");
+
+    std::thread::spawn(move || {
+        let mut logins = 0;
+        for mut request in server.incoming_requests() {
+            let path = request.url().to_string();
+            let form = path.split("form=").nth(1).unwrap_or_default().to_string();
+            let stok = path
+                .split(";stok=")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or_default()
+                .to_string();
+
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+
+            let plain = match form.as_str() {
+                "keys" => Some(json!({"result": {"password": [FAKE_ROUTER_RSA_N, "010001"]}})),
+                "auth" => Some(json!({"result": {"key": [FAKE_ROUTER_RSA_N, "010001"], "seq": 1}})),
+                _ => None,
+            };
+            let response = match plain {
+                Some(response) => response.to_string(),
+                None => {
+                    let query = Url::parse(&format!("http://localhost/?{}", body)).unwrap();
+                    let data = query
+                        .query_pairs()
+                        .find(|(key, _)| key == "data")
+                        .map(|(_, data)| data.into_owned())
+                        .unwrap_or_default();
+                    let data: serde_json::Value = session.decrypt_response(&data).unwrap();
+
+                    let response = if form == "login" {
+                        logins += 1;
+                        json!({"result": {"stok": format!("stok{}", logins)}})
+                    } else if stok != format!("stok{}", logins) {
+                        json!({"success": false, "error_code": -40401})
+                    } else {
+                        handle(&form, &data)
+                    };
+                    serde_json::to_string(&session.encrypt_payload_json(&response, false)).unwrap()
+                }
+            };
+
+            let _ = request.respond(tiny_http::Response::from_string(response));
+        }
+    });
+
+    url
+}
+
+#[test]
+fn set_client_access_test() {
+    use std::sync::{Arc, Mutex};
+
+    // AA-BB-CC-DD-EE-01 is listed, returns whether the list changed and the
+    //   last request sent.
+    let run = |access_mode: &'static str, mac: &str, allowed: bool| {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+        let url = spawn_fake_router(move |form, request| {
+            let operation = request["operation"].as_str().unwrap_or_default();
+            log.lock().unwrap().push(format!("{} {}", form, operation));
+            match form {
+                "mode" => json!({"result": {"enable": true, "access_mode": access_mode}}),
+                _ => json!({"result": {"client_list": [{"mac": "AA-BB-CC-DD-EE-01"}]}}),
+            }
+        });
+
+        let mut api = MercusysHTTP::new(url);
+        api.login("admin", "secret").unwrap();
+        let changed = api
+            .set_client_access(mac.parse().unwrap(), "", allowed)
+            .unwrap();
+        let last = requests.lock().unwrap().last().cloned().unwrap();
+        (changed, last)
+    };
+
+    let added = |form: &str| (true, format!("{} add", form));
+    let removed = |form: &str| (true, format!("{} remove", form));
+
+    assert_eq!(
+        run("block", "aa:bb:cc:dd:ee:02", false),
+        added("block_list")
+    );
+    assert_eq!(
+        run("block", "aa:bb:cc:dd:ee:01", true),
+        removed("block_list")
+    );
+    assert_eq!(run("allow", "aa:bb:cc:dd:ee:02", true), added("allow_list"));
+    assert_eq!(
+        run("allow", "aa:bb:cc:dd:ee:01", false),
+        removed("allow_list")
+    );
+
+    // Already as wanted, only the list was read.
+    assert_eq!(
+        run("block", "aa:bb:cc:dd:ee:01", false),
+        (false, "block_list read".into())
+    );
+    assert_eq!(
+        run("allow", "aa:bb:cc:dd:ee:01", true),
+        (false, "allow_list read".into())
+    );
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A MAC address, accepted as "aa:bb:cc:dd:ee:ff", "AA-BB-CC-DD-EE-FF" or
+///   "aabbccddeeff", and written the way the router does, "AA-BB-CC-DD-EE-FF".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MacAddress([u8; 6]);
+
+impl MacAddress {
+    /// Whether `other`, as returned by the router, is this address.
+    pub fn matches(&self, other: &str) -> bool {
+        other.parse::<MacAddress>() == Ok(*self)
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid MAC address \"{}\"", s);
+
+        let digits: String = s.chars().filter(|c| !matches!(c, ':' | '-')).collect();
+        if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 6];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(MacAddress(bytes))
+    }
+}
+
+impl TryFrom<String> for MacAddress {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<MacAddress> for String {
+    fn from(mac: MacAddress) -> Self {
+        mac.to_string()
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02X}-{:02X}-{:02X}-{:02X}-{:02X}-{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+#[test]
+fn mac_address_test() {
+    let mac: MacAddress = "aa:bb:cc:0d:0e:0f".parse().unwrap();
+    assert_eq!(mac.to_string(), "AA-BB-CC-0D-0E-0F");
+    assert_eq!("AABBCC0D0E0F".parse::<MacAddress>(), Ok(mac));
+    assert!(mac.matches("AA-BB-CC-0D-0E-0F"));
+    assert!(!mac.matches("AA-BB-CC-0D-0E-00"));
+
+    assert!("aa:bb:cc:dd:ee".parse::<MacAddress>().is_err());
+    assert!("aa:bb:cc:dd:ee:gg".parse::<MacAddress>().is_err());
+}
@@ -1,9 +1,30 @@
 pub mod api_response;
 
+pub mod api_access_control;
+pub mod api_account;
+pub mod api_backup;
+pub mod api_client_list;
 pub mod api_device_list;
+pub mod api_diagnostic;
+pub mod api_firmware;
+pub mod api_gateway;
+pub mod api_guest;
+pub mod api_led;
+pub mod api_log;
 pub mod api_login_keys;
 pub mod api_logout;
+pub mod api_operation_mode;
+pub mod api_parental;
+pub mod api_port_forwarding;
 pub mod api_reboot;
+pub mod api_reservation;
+pub mod api_speedtest;
+pub mod api_wan;
+pub mod api_wireless;
 
 pub mod crypto;
+pub mod encoding;
+pub mod error;
 pub mod http;
+pub mod mac_address;
+pub mod rolling;
@@ -0,0 +1,238 @@
+// Rolling reboot: restart the mesh one node at a time, so the house never
+// goes completely offline.
+//
+// Satellites are rebooted deepest first (following `parent_device_id`), and
+// each one has to rejoin the mesh before we move on. The main unit goes last,
+// since rebooting it takes the router (and our session) down.
+
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use super::api_device_list::make_test_device;
+use super::api_device_list::SingleDeviceItem;
+use super::error::MercusysResult;
+use super::http::MercusysHTTP;
+
+#[derive(Debug, Clone)]
+pub struct RollingRebootOptions {
+    /// How long a single node may take to rejoin the mesh.
+    pub node_timeout: Duration,
+
+    /// Delay between device list polls.
+    pub poll_interval: Duration,
+
+    /// Stop at the first node that fails to rejoin,
+    ///   leaving the main unit untouched.
+    pub abort_on_failure: bool,
+}
+
+impl Default for RollingRebootOptions {
+    fn default() -> Self {
+        RollingRebootOptions {
+            node_timeout: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(5),
+            abort_on_failure: false,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct RollingRebootReport {
+    /// MAC addresses of nodes that were rebooted and came back.
+    pub rejoined: Vec<String>,
+
+    /// MAC addresses of nodes that did not rejoin in time.
+    pub failed: Vec<String>,
+
+    /// Set if the main unit was rebooted at the end.
+    pub main_reboot_time: Option<i64>,
+}
+
+impl RollingRebootReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Number of hops between a node and the main unit.
+fn node_depth(device: &SingleDeviceItem, by_id: &HashMap<&str, &SingleDeviceItem>) -> usize {
+    let mut depth = 0;
+    let mut current = device;
+
+    // Bounded, in case the router ever reports a loop.
+    while depth < by_id.len() {
+        let parent_id = match current.parent_device_id.as_deref() {
+            Some(id) if !id.is_empty() => id,
+            _ => break,
+        };
+
+        depth += 1;
+        match by_id.get(parent_id) {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+
+    depth
+}
+
+/// Leaf satellites first, the main unit last.
+pub fn reboot_order(devices: &[SingleDeviceItem]) -> Vec<&SingleDeviceItem> {
+    let by_id: HashMap<&str, &SingleDeviceItem> = devices
+        .iter()
+        .filter_map(|device| device.device_id.as_deref().map(|id| (id, device)))
+        .collect();
+
+    let mut ordered: Vec<(bool, usize, &SingleDeviceItem)> = devices
+        .iter()
+        .map(|device| (device.is_main(), node_depth(device, &by_id), device))
+        .collect();
+
+    // Stable sort, nodes at the same depth keep the router's order.
+    ordered.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    ordered.into_iter().map(|(_, _, device)| device).collect()
+}
+
+pub fn describe(device: &SingleDeviceItem) -> String {
+    let nickname = device.nickname();
+    if nickname.is_empty() {
+        device.mac.clone()
+    } else {
+        format!("{} ({})", nickname, device.mac)
+    }
+}
+
+/// Used when the router doesn't say how long a reboot takes.
+const DEFAULT_REBOOT_TIME: Duration = Duration::from_secs(90);
+
+/// A node is back once it was seen offline and connected again, or, when it
+///   rebooted between two polls, once it's connected after the reboot time.
+fn has_rejoined(
+    went_offline: bool,
+    connected: bool,
+    elapsed: Duration,
+    reboot_time: Duration,
+) -> bool {
+    connected && (went_offline || elapsed >= reboot_time)
+}
+
+/// Wait for a node to drop off the mesh and come back. `reboot_time` is the
+///   router's estimate in seconds, 0 if unknown.
+pub fn wait_for_rejoin(
+    api: &mut MercusysHTTP,
+    mac: &str,
+    reboot_time: i64,
+    options: &RollingRebootOptions,
+) -> bool {
+    let reboot_time = match reboot_time {
+        seconds if seconds > 0 => Duration::from_secs(seconds as u64),
+        _ => DEFAULT_REBOOT_TIME,
+    };
+    let started = Instant::now();
+    let deadline = started + options.node_timeout;
+    let mut went_offline = false;
+
+    while Instant::now() < deadline {
+        sleep(options.poll_interval);
+
+        let devices = match api.get_device_list() {
+            Ok(devices) => devices.result.device_list,
+            Err(err) => {
+                eprintln!("  failed to poll device list: {}", err);
+                continue;
+            }
+        };
+
+        let connected = devices
+            .iter()
+            .any(|device| device.mac == mac && device.is_connected());
+
+        if has_rejoined(went_offline, connected, started.elapsed(), reboot_time) {
+            return true;
+        }
+        went_offline |= !connected;
+    }
+
+    false
+}
+
+pub fn rolling_reboot(
+    api: &mut MercusysHTTP,
+    options: &RollingRebootOptions,
+) -> MercusysResult<RollingRebootReport> {
+    let devices = api.get_device_list()?.result.device_list;
+    let mut report = RollingRebootReport::default();
+
+    for device in reboot_order(&devices) {
+        if device.is_main() {
+            eprintln!("rebooting main unit {}...", describe(device));
+            let resp = api.reboot_devices(&[device.mac.as_str()])?;
+            report.main_reboot_time = Some(resp.result.reboot_time);
+            continue;
+        }
+
+        eprintln!("rebooting {}...", describe(device));
+        let resp = api.reboot_devices(&[device.mac.as_str()])?;
+
+        if wait_for_rejoin(api, &device.mac, resp.result.reboot_time, options) {
+            eprintln!("  {} rejoined the mesh", device.mac);
+            report.rejoined.push(device.mac.clone());
+        } else {
+            eprintln!(
+                "  {} did not rejoin within {}s",
+                device.mac,
+                options.node_timeout.as_secs()
+            );
+            report.failed.push(device.mac.clone());
+
+            if options.abort_on_failure {
+                eprintln!("abort rolling reboot.");
+                break;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[test]
+fn reboot_order_test() {
+    let node = |mac: &str, device_id: &str, parent: Option<&str>, role: &str| SingleDeviceItem {
+        device_id: Some(device_id.into()),
+        parent_device_id: parent.map(|p| p.into()),
+        ..make_test_device(mac, role)
+    };
+
+    // main <- a <- b <- c, main <- d
+    let devices = vec![
+        node("main", "0", None, "master"),
+        node("a", "1", Some("0"), "slave"),
+        node("d", "4", Some("0"), "slave"),
+        node("b", "2", Some("1"), "slave"),
+        node("c", "3", Some("2"), "slave"),
+    ];
+
+    let actual: Vec<&str> = reboot_order(&devices)
+        .iter()
+        .map(|device| device.mac.as_str())
+        .collect();
+
+    assert_eq!(actual, vec!["c", "b", "a", "d", "main"]);
+}
+
+#[test]
+fn has_rejoined_test() {
+    let reboot_time = Duration::from_secs(90);
+    let early = Duration::from_secs(10);
+    let late = Duration::from_secs(120);
+
+    // Still up, the reboot has not started yet.
+    assert!(!has_rejoined(false, true, early, reboot_time));
+    // Seen offline, then back.
+    assert!(has_rejoined(true, true, early, reboot_time));
+    assert!(!has_rejoined(true, false, late, reboot_time));
+    // Rebooted between two polls.
+    assert!(has_rejoined(false, true, late, reboot_time));
+}
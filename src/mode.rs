@@ -0,0 +1,154 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+use reqwest::Url;
+
+use crate::mercusys::api_operation_mode::OPERATION_MODES;
+use crate::mercusys::http::MercusysHTTP;
+use crate::prompt::confirm;
+
+#[derive(Args, Debug, Clone)]
+pub struct ModeArgs {
+    #[command(subcommand)]
+    pub action: ModeAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ModeAction {
+    /// Show whether the mesh runs as router or access point.
+    Show,
+
+    /// Switch between router and access point mode. The whole mesh restarts.
+    Set {
+        #[arg(value_parser = OPERATION_MODES)]
+        mode: String,
+
+        /// address the router will be at afterwards, --url if omitted.
+        #[arg(long)]
+        new_url: Option<Url>,
+
+        /// seconds to wait for the router to come back.
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+
+        /// don't ask for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+/// Modes we don't know are shown as the router reports them.
+fn describe_mode(mode: &str) -> &str {
+    match mode {
+        "ap" => "access point",
+        "router" => "router",
+        other => other,
+    }
+}
+
+fn warning(mode: &str, new_url: &Url) -> String {
+    let what = match mode {
+        "ap" => {
+            "Access point mode turns off DHCP, NAT and the firewall of the mesh: port \
+             forwarding, reservations, parental controls and access control stop working, \
+             and the router gets its address from the upstream router."
+        }
+        "router" => {
+            "Router mode makes the mesh the gateway again, with its own DHCP server and NAT \
+             behind the modem, and its own LAN address (usually http://192.168.68.1)."
+        }
+        _ => "The mesh changes its operation mode.",
+    };
+
+    format!(
+        "{} Every node restarts and all clients disconnect. Afterwards the router is \
+         expected at {}, use --new-url if that's wrong, or find it from the upstream \
+         router or the app.",
+        what, new_url
+    )
+}
+
+/// Wait until the router answers at its current base URL, in `mode`.
+fn wait_for_mode(api: &mut MercusysHTTP, mode: &str, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(10));
+
+        match api.with_relogin(|api| api.get_operation_mode()) {
+            Ok(resp) if resp.result.mode == mode => return Ok(()),
+            Ok(resp) => eprintln!("  still in {} mode...", describe_mode(&resp.result.mode)),
+            Err(err) => eprintln!("  waiting for {}: {}", api.base_url(), err),
+        }
+    }
+
+    Err(format!(
+        "router did not come back at {} within {}s",
+        api.base_url(),
+        timeout.as_secs()
+    ))
+}
+
+pub fn run_mode(api: &mut MercusysHTTP, args: &ModeArgs, dry_run: bool) -> Result<(), String> {
+    let current = api
+        .get_operation_mode()
+        .map_err(|err| format!("failed to read operation mode: {}", err))?
+        .result;
+
+    let ModeAction::Set {
+        mode,
+        new_url,
+        timeout,
+        yes,
+    } = &args.action
+    else {
+        println!("{}", describe_mode(&current.mode));
+        return Ok(());
+    };
+
+    if &current.mode == mode {
+        eprintln!("already in {} mode.", describe_mode(mode));
+        return Ok(());
+    }
+
+    let new_url = new_url.clone().unwrap_or_else(|| api.base_url().clone());
+    let warning = warning(mode, &new_url);
+    if dry_run {
+        eprintln!("{}", warning);
+        eprintln!("dry run mode, would switch to {} mode", describe_mode(mode));
+        return Ok(());
+    }
+    if !yes && !confirm(warning.as_str()) {
+        return Err("mode switch cancelled".into());
+    }
+
+    let resp = api
+        .set_operation_mode(mode)
+        .map_err(|err| format!("failed to switch operation mode: {}", err))?;
+    eprintln!(
+        "switching to {} mode, router expects {}s...",
+        describe_mode(mode),
+        resp.result.wait_time
+    );
+
+    api.set_base_url(new_url);
+    wait_for_mode(api, mode, Duration::from_secs(*timeout))?;
+    eprintln!(
+        "router is back at {} in {} mode.",
+        api.base_url(),
+        describe_mode(mode)
+    );
+    Ok(())
+}
+
+#[test]
+fn describe_mode_test() {
+    assert_eq!(describe_mode("ap"), "access point");
+    assert_eq!(describe_mode("router"), "router");
+    assert_eq!(describe_mode("bridge"), "bridge");
+
+    let url: Url = "http://192.168.1.20".parse().unwrap();
+    assert!(warning("ap", &url).starts_with("Access point mode turns off DHCP"));
+    assert!(warning("router", &url).starts_with("Router mode"));
+    assert!(warning("bridge", &url).contains("http://192.168.1.20/"));
+}
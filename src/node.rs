@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+
+#[cfg(test)]
+use crate::mercusys::api_device_list::make_test_device;
+use crate::mercusys::api_device_list::SingleDeviceItem;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::mercusys::rolling::describe;
+use crate::prompt::confirm;
+
+#[derive(Args, Debug, Clone)]
+pub struct NodeArgs {
+    #[command(subcommand)]
+    pub action: NodeAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum NodeAction {
+    /// Remove a satellite from the mesh.
+    Remove {
+        #[arg(long)]
+        mac: MacAddress,
+
+        /// don't ask for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+
+    /// Look for a new satellite and wait until it has joined the mesh.
+    Add {
+        /// seconds to wait for the new node.
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+    },
+
+    /// Stop looking for new satellites.
+    StopAdding,
+}
+
+/// The satellite to remove, checked before anything else (dry run included).
+fn find_removable(
+    devices: &[SingleDeviceItem],
+    mac: MacAddress,
+) -> Result<&SingleDeviceItem, String> {
+    let device = devices
+        .iter()
+        .find(|device| mac.matches(&device.mac))
+        .ok_or_else(|| format!("no node with MAC {} in the mesh", mac))?;
+    if device.is_main() {
+        return Err(format!(
+            "{} is the main unit, it can't be removed",
+            describe(device)
+        ));
+    }
+    Ok(device)
+}
+
+fn remove(api: &mut MercusysHTTP, mac: MacAddress, yes: bool, dry_run: bool) -> Result<(), String> {
+    let devices = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list;
+    let device = find_removable(&devices, mac)?;
+
+    if dry_run {
+        eprintln!("dry run mode, would remove {}", describe(device));
+        return Ok(());
+    }
+
+    let question = format!(
+        "This removes {} from the mesh, it has to be reset to join again.",
+        describe(device)
+    );
+    if !yes && !confirm(question.as_str()) {
+        return Err("remove cancelled".into());
+    }
+
+    api.remove_device(mac)
+        .map_err(|err| format!("failed to remove {}: {}", mac, err))?;
+    eprintln!("{} removed.", describe(device));
+    Ok(())
+}
+
+fn add(api: &mut MercusysHTTP, timeout: Duration, dry_run: bool) -> Result<(), String> {
+    let known: HashSet<String> = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list
+        .into_iter()
+        .map(|device| device.mac)
+        .collect();
+
+    if dry_run {
+        eprintln!("dry run mode, would look for new satellites.");
+        return Ok(());
+    }
+
+    let resp = api
+        .set_onboarding(true)
+        .map_err(|err| format!("failed to start onboarding: {}", err))?;
+    eprintln!(
+        "looking for new satellites ({}s), power the new node on close to an existing one...",
+        resp.result.remaining_time
+    );
+
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(5));
+
+        let devices = match api.with_relogin(|api| api.get_device_list()) {
+            Ok(devices) => devices.result.device_list,
+            Err(err) => {
+                eprintln!("  failed to poll device list: {}", err);
+                continue;
+            }
+        };
+
+        for device in devices.iter().filter(|device| !known.contains(&device.mac)) {
+            if device.is_connected() {
+                // Usually stopped by the router already, just in case.
+                let _ = api.set_onboarding(false);
+                eprintln!("{} joined the mesh.", describe(device));
+                println!("{}", device.mac);
+                return Ok(());
+            }
+            eprintln!("  {} found, {}", device.mac, device.group_status);
+        }
+    }
+
+    let _ = api.set_onboarding(false);
+    Err(format!("no new node joined within {}s", timeout.as_secs()))
+}
+
+pub fn run_node(api: &mut MercusysHTTP, args: &NodeArgs, dry_run: bool) -> Result<(), String> {
+    match &args.action {
+        NodeAction::Remove { mac, yes } => remove(api, *mac, *yes, dry_run),
+        NodeAction::Add { timeout } => add(api, Duration::from_secs(*timeout), dry_run),
+        NodeAction::StopAdding => {
+            if dry_run {
+                eprintln!("dry run mode, would stop looking for new satellites.");
+                return Ok(());
+            }
+
+            api.set_onboarding(false)
+                .map_err(|err| format!("failed to stop onboarding: {}", err))?;
+            eprintln!("stopped looking for new satellites.");
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn find_removable_test() {
+    let devices = vec![
+        make_test_device("AA-AA-AA-AA-AA-01", "master"),
+        make_test_device("AA-AA-AA-AA-AA-02", "slave"),
+    ];
+    let mac = |mac: &str| mac.parse::<MacAddress>().unwrap();
+
+    let device = find_removable(&devices, mac("aa:aa:aa:aa:aa:02")).unwrap();
+    assert_eq!(device.mac, "AA-AA-AA-AA-AA-02");
+    assert!(find_removable(&devices, mac("AA-AA-AA-AA-AA-01")).is_err());
+    assert!(find_removable(&devices, mac("AA-AA-AA-AA-AA-03")).is_err());
+}
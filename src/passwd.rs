@@ -0,0 +1,122 @@
+// Admin password rotation.
+//
+// The new password is validated, changed on the router, and written to the
+// `--password-file` right away, since from then on it's the only one that
+// works. A fresh login with it then proves the router took it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::mercusys::api_account::validate_password;
+use crate::mercusys::http::MercusysHTTP;
+use crate::util::write_private_file;
+
+#[derive(Args, Debug, Clone)]
+#[group(required = true, multiple = false)]
+pub struct PasswdArgs {
+    /// new admin password.
+    #[arg(long)]
+    pub new_password: Option<String>,
+
+    /// read the new admin password from this file.
+    #[arg(long)]
+    pub new_password_file: Option<PathBuf>,
+}
+
+/// First line of the file, for `--password-file` style options.
+pub fn read_password_file(path: &Path) -> Result<String, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    Ok(content.lines().next().unwrap_or_default().to_string())
+}
+
+/// Replace the file content, only readable by the current user. A symlink,
+///   e.g. into a secrets store, is written through rather than replaced.
+fn write_password_file(path: &Path, password: &str) -> Result<(), String> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    write_private_file(&target, &format!("{}\n", password))
+        .map_err(|err| format!("failed to write {}: {}", target.display(), err))
+}
+
+pub fn run_passwd(
+    api: &mut MercusysHTTP,
+    args: &PasswdArgs,
+    user: &str,
+    old_password: &str,
+    password_file: Option<&Path>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let new_password = match (&args.new_password, &args.new_password_file) {
+        (Some(password), _) => password.clone(),
+        (None, Some(path)) => read_password_file(path)?,
+        (None, None) => unreachable!(),
+    };
+    if new_password == old_password {
+        return Err("the new password is the same as the current one".into());
+    }
+    validate_password(&new_password)?;
+
+    if dry_run {
+        eprintln!("dry run mode, would change the admin password.");
+        return Ok(());
+    }
+
+    api.change_password(old_password, &new_password)
+        .map_err(|err| format!("failed to change password: {}", err))?;
+    eprintln!("password changed.");
+
+    match password_file {
+        Some(path) => {
+            write_password_file(path, &new_password).map_err(|err| {
+                format!(
+                    "password changed, but {}. Update it by hand, \
+                     the old password no longer works.",
+                    err
+                )
+            })?;
+            eprintln!("{} updated.", path.display());
+        }
+        None => eprintln!("remember to update the password wherever this tool is run from."),
+    }
+
+    api.login(user, &new_password).map_err(|err| {
+        format!(
+            "password changed, but login with the new one failed: {}",
+            err
+        )
+    })?;
+    eprintln!("login with the new password works.");
+
+    Ok(())
+}
+
+#[test]
+fn password_file_test() {
+    let dir = std::env::temp_dir().join(format!("mercusys-passwd-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("password");
+
+    fs::write(&path, "old\n").unwrap();
+    write_password_file(&path, "n3w-secret").unwrap();
+    assert_eq!(read_password_file(&path), Ok("n3w-secret".into()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let link = dir.join("password-link");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        write_password_file(&link, "n3w-secret-2").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(read_password_file(&path), Ok("n3w-secret-2".into()));
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,183 @@
+use std::net::Ipv4Addr;
+
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_port_forwarding::{PortForwardingRule, PortRange, Protocol};
+use crate::mercusys::encoding::encode_text;
+use crate::mercusys::http::MercusysHTTP;
+
+#[derive(Args, Debug, Clone)]
+pub struct PortsArgs {
+    #[command(subcommand)]
+    pub action: PortsAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum PortsAction {
+    /// List port forwarding rules.
+    List,
+
+    /// Forward external ports to a device on the LAN.
+    Add {
+        /// service name, shown by the app.
+        #[arg(long)]
+        name: String,
+
+        /// tcp, udp or all.
+        #[arg(long, default_value = "all")]
+        protocol: Protocol,
+
+        /// external port or range, e.g. 25565 or 27015-27030.
+        #[arg(long)]
+        external: PortRange,
+
+        /// internal port or range, the external one if omitted.
+        #[arg(long)]
+        internal: Option<PortRange>,
+
+        /// device to forward to.
+        #[arg(long)]
+        ip: Ipv4Addr,
+    },
+
+    /// Delete a port forwarding rule, by the ID `list` shows.
+    Delete { id: String },
+
+    /// List UPnP mappings, or turn UPnP on or off. Single mappings can't
+    ///   be removed.
+    Upnp {
+        #[arg(value_parser = ["on", "off"])]
+        state: Option<String>,
+    },
+}
+
+fn list(api: &mut MercusysHTTP) -> Result<(), String> {
+    let rules = api
+        .get_port_forwarding_rules()
+        .map_err(|err| format!("failed to read port forwarding rules: {}", err))?
+        .result
+        .rule_list;
+
+    println!(
+        "{:<4}  {:<20}  {:<5}  {:<11}  {:<15}  {:<11}  ENABLED",
+        "ID", "NAME", "PROTO", "EXTERNAL", "INTERNAL IP", "INTERNAL"
+    );
+    for rule in &rules {
+        println!(
+            "{:<4}  {:<20}  {:<5}  {:<11}  {:<15}  {:<11}  {}",
+            rule.id,
+            rule.service_name(),
+            rule.protocol,
+            rule.external_port,
+            rule.internal_ip,
+            rule.internal_port,
+            rule.enable
+        );
+    }
+    Ok(())
+}
+
+fn upnp(api: &mut MercusysHTTP, state: Option<&str>, dry_run: bool) -> Result<(), String> {
+    if let Some(state) = state {
+        let enable = state == "on";
+        if dry_run {
+            eprintln!("dry run mode, would turn UPnP {}", state);
+            return Ok(());
+        }
+        api.set_upnp(enable)
+            .map_err(|err| format!("failed to turn UPnP {}: {}", state, err))?;
+        eprintln!("UPnP turned {}.", state);
+        return Ok(());
+    }
+
+    let enabled = api
+        .get_upnp_settings()
+        .map_err(|err| format!("failed to read UPnP settings: {}", err))?
+        .result
+        .enable;
+    println!("UPnP: {}", if enabled { "on" } else { "off" });
+    if !enabled {
+        return Ok(());
+    }
+
+    let mappings = api
+        .get_upnp_mappings()
+        .map_err(|err| format!("failed to read UPnP mappings: {}", err))?
+        .result
+        .upnp_list;
+    println!(
+        "{:<5}  {:<8}  {:<15}  {:<8}  DESCRIPTION",
+        "PROTO", "EXTERNAL", "INTERNAL IP", "INTERNAL"
+    );
+    for mapping in &mappings {
+        println!(
+            "{:<5}  {:<8}  {:<15}  {:<8}  {}",
+            mapping.protocol,
+            mapping.external_port,
+            mapping.internal_ip,
+            mapping.internal_port,
+            mapping.description()
+        );
+    }
+    Ok(())
+}
+
+pub fn run_ports(api: &mut MercusysHTTP, args: &PortsArgs, dry_run: bool) -> Result<(), String> {
+    match &args.action {
+        PortsAction::List => list(api),
+        PortsAction::Add {
+            name,
+            protocol,
+            external,
+            internal,
+            ip,
+        } => {
+            let rule = PortForwardingRule {
+                id: String::new(),
+                name: encode_text(name),
+                protocol: *protocol,
+                external_port: *external,
+                internal_port: internal.unwrap_or(*external),
+                internal_ip: *ip,
+                enable: true,
+            };
+            let summary = format!("{} {} -> {}:{}", protocol, external, ip, rule.internal_port);
+
+            if dry_run {
+                api.check_port_forwarding_rule(&rule)
+                    .map_err(|err| format!("can't forward {}: {}", summary, err))?;
+                eprintln!("dry run mode, would forward {}", summary);
+                return Ok(());
+            }
+            api.add_port_forwarding_rule(&rule)
+                .map_err(|err| format!("can't forward {}: {}", summary, err))?;
+            eprintln!("forwarding {}.", summary);
+            Ok(())
+        }
+        PortsAction::Delete { id } => {
+            let rules = api
+                .get_port_forwarding_rules()
+                .map_err(|err| format!("failed to read port forwarding rules: {}", err))?
+                .result
+                .rule_list;
+            let rule = rules
+                .iter()
+                .find(|rule| &rule.id == id)
+                .ok_or_else(|| format!("no port forwarding rule with ID {}", id))?;
+            let summary = format!(
+                "{} {} -> {}:{}",
+                rule.protocol, rule.external_port, rule.internal_ip, rule.internal_port
+            );
+
+            if dry_run {
+                eprintln!("dry run mode, would delete {}", summary);
+                return Ok(());
+            }
+            api.remove_port_forwarding_rule(id)
+                .map_err(|err| format!("failed to delete rule {}: {}", id, err))?;
+            eprintln!("deleted {}.", summary);
+            Ok(())
+        }
+        PortsAction::Upnp { state } => upnp(api, state.as_deref(), dry_run),
+    }
+}
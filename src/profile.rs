@@ -0,0 +1,264 @@
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_parental::{ParentalProfile, ParentalProfileListResult};
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::util::{parse_duration, parse_time_range};
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+#[derive(Args, Debug, Clone)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub action: ProfileAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProfileAction {
+    /// List parental control profiles and their devices.
+    List,
+
+    /// Cut internet access for every device in a profile.
+    Pause { name: String },
+
+    /// Give internet access back to a paused profile.
+    Resume { name: String },
+
+    /// Add devices to a profile.
+    Assign {
+        name: String,
+
+        /// client MAC addresses.
+        #[arg(required = true)]
+        macs: Vec<MacAddress>,
+    },
+
+    /// Remove devices from a profile.
+    Unassign {
+        name: String,
+
+        /// client MAC addresses.
+        #[arg(required = true)]
+        macs: Vec<MacAddress>,
+    },
+
+    /// Set the daily time limit and/or bedtime of a profile.
+    Limits(ProfileLimitsArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProfileLimitsArgs {
+    pub name: String,
+
+    /// online time allowed per day, e.g. 2h or 90m, "off" to remove the limit.
+    #[arg(long)]
+    pub daily_limit: Option<String>,
+
+    /// no internet during this time, e.g. 21:00-07:00, "off" to disable.
+    #[arg(long)]
+    pub bedtime: Option<String>,
+}
+
+impl ProfileLimitsArgs {
+    fn apply(&self, profile: &mut ParentalProfile) -> Result<(), String> {
+        match self.daily_limit.as_deref() {
+            None => {}
+            Some("off") => profile.time_limit.enable = false,
+            Some(limit) => {
+                let minutes = parse_duration(limit)?.as_secs() / 60;
+                if !(1..=MINUTES_PER_DAY).contains(&minutes) {
+                    return Err(format!(
+                        "daily limit must be between a minute and 24h, got {}",
+                        limit
+                    ));
+                }
+                profile.time_limit.enable = true;
+                profile.time_limit.daily_minutes = minutes as i64;
+            }
+        }
+
+        match self.bedtime.as_deref() {
+            None => {}
+            Some("off") => profile.bedtime.enable = false,
+            Some(range) => {
+                let (start, end) = parse_time_range(range)?;
+                profile.bedtime.enable = true;
+                profile.bedtime.start_time = start;
+                profile.bedtime.end_time = end;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn find_profile(
+    profiles: &ParentalProfileListResult,
+    name: &str,
+) -> Result<ParentalProfile, String> {
+    profiles.find(name).cloned().ok_or_else(|| {
+        let names: Vec<String> = profiles
+            .profile_list
+            .iter()
+            .map(|profile| profile.name())
+            .collect();
+        format!(
+            "no profile named \"{}\", available: {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+fn print_profile(profile: &ParentalProfile) {
+    let limit = if profile.time_limit.enable {
+        format!("{} min/day", profile.time_limit.daily_minutes)
+    } else {
+        "none".into()
+    };
+    let bedtime = if profile.bedtime.enable {
+        format!(
+            "{}-{}",
+            profile.bedtime.start_time, profile.bedtime.end_time
+        )
+    } else {
+        "none".into()
+    };
+    println!(
+        "{}{}",
+        profile.name(),
+        if profile.internet_paused {
+            " (paused)"
+        } else {
+            ""
+        }
+    );
+    println!("  time limit: {}", limit);
+    println!("  bedtime:    {}", bedtime);
+    println!("  devices:    {}", profile.client_list.join(", "));
+}
+
+fn pause(
+    api: &mut MercusysHTTP,
+    profile: &ParentalProfile,
+    paused: bool,
+    dry_run: bool,
+) -> Result<(), String> {
+    let action = if paused { "pause" } else { "resume" };
+    if profile.internet_paused == paused {
+        eprintln!("{} is already {}d.", profile.name(), action);
+        return Ok(());
+    }
+    if dry_run {
+        eprintln!("dry run mode, would {} {}", action, profile.name());
+        return Ok(());
+    }
+
+    api.set_profile_paused(profile, paused)
+        .map_err(|err| format!("failed to {} {}: {}", action, profile.name(), err))?;
+    eprintln!("{} {}d.", profile.name(), action);
+    Ok(())
+}
+
+fn write_profile(
+    api: &mut MercusysHTTP,
+    profile: &ParentalProfile,
+    dry_run: bool,
+) -> Result<(), String> {
+    if dry_run {
+        eprintln!("dry run mode, would write:");
+        print_profile(profile);
+        return Ok(());
+    }
+
+    api.set_parental_profile(profile)
+        .map_err(|err| format!("failed to update {}: {}", profile.name(), err))?;
+    print_profile(profile);
+    Ok(())
+}
+
+pub fn run_profile(
+    api: &mut MercusysHTTP,
+    args: &ProfileArgs,
+    dry_run: bool,
+) -> Result<(), String> {
+    let profiles = api
+        .get_parental_profiles()
+        .map_err(|err| format!("failed to read parental control profiles: {}", err))?
+        .result;
+
+    match &args.action {
+        ProfileAction::List => {
+            for profile in &profiles.profile_list {
+                print_profile(profile);
+            }
+            Ok(())
+        }
+        ProfileAction::Pause { name } => pause(api, &find_profile(&profiles, name)?, true, dry_run),
+        ProfileAction::Resume { name } => {
+            pause(api, &find_profile(&profiles, name)?, false, dry_run)
+        }
+        ProfileAction::Assign { name, macs } => {
+            let mut profile = find_profile(&profiles, name)?;
+            for mac in macs {
+                // A device can only belong to one profile.
+                if let Some(other) = profiles
+                    .profile_list
+                    .iter()
+                    .find(|other| other.profile_id != profile.profile_id && other.has_client(*mac))
+                {
+                    return Err(format!("{} already belongs to {}", mac, other.name()));
+                }
+                profile.add_client(*mac);
+            }
+            write_profile(api, &profile, dry_run)
+        }
+        ProfileAction::Unassign { name, macs } => {
+            let mut profile = find_profile(&profiles, name)?;
+            profile.remove_clients(macs);
+            write_profile(api, &profile, dry_run)
+        }
+        ProfileAction::Limits(limits_args) => {
+            let mut profile = find_profile(&profiles, &limits_args.name)?;
+            limits_args.apply(&mut profile)?;
+            write_profile(api, &profile, dry_run)
+        }
+    }
+}
+
+#[test]
+fn profile_limits_apply_test() {
+    let limits = |daily_limit: Option<&str>, bedtime: Option<&str>| ProfileLimitsArgs {
+        name: "Kids".into(),
+        daily_limit: daily_limit.map(|limit| limit.into()),
+        bedtime: bedtime.map(|range| range.into()),
+    };
+
+    let mut profile = ParentalProfile::default();
+    limits(Some("1h30m"), Some("21:00-7:00"))
+        .apply(&mut profile)
+        .unwrap();
+    assert!(profile.time_limit.enable);
+    assert_eq!(profile.time_limit.daily_minutes, 90);
+    assert!(profile.bedtime.enable);
+    assert_eq!(
+        (
+            profile.bedtime.start_time.as_str(),
+            profile.bedtime.end_time.as_str()
+        ),
+        ("21:00", "07:00")
+    );
+
+    // Only what was given changes.
+    limits(Some("off"), None).apply(&mut profile).unwrap();
+    assert!(!profile.time_limit.enable);
+    assert!(profile.bedtime.enable);
+
+    limits(None, Some("off")).apply(&mut profile).unwrap();
+    assert!(!profile.bedtime.enable);
+
+    assert!(limits(Some("30s"), None).apply(&mut profile).is_err());
+    assert!(limits(Some("2d"), None).apply(&mut profile).is_err());
+    assert!(limits(Some("24h"), None).apply(&mut profile).is_ok());
+    assert!(limits(None, Some("21:00")).apply(&mut profile).is_err());
+}
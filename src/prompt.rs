@@ -0,0 +1,10 @@
+use std::io::{self, BufRead, Write};
+
+/// Ask on the terminal, only a literal "yes" goes ahead.
+pub fn confirm(question: &str) -> bool {
+    eprint!("{} Type \"yes\" to continue: ", question);
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).is_ok() && answer.trim() == "yes"
+}
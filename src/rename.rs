@@ -0,0 +1,48 @@
+use clap::Args;
+
+use crate::mercusys::api_device_list::validate_nickname;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+
+#[derive(Args, Debug, Clone)]
+pub struct RenameArgs {
+    /// node to rename.
+    #[arg(long)]
+    pub mac: MacAddress,
+
+    /// new nickname, e.g. "Garage".
+    pub nickname: String,
+}
+
+pub fn run_rename(api: &mut MercusysHTTP, args: &RenameArgs, dry_run: bool) -> Result<(), String> {
+    validate_nickname(&args.nickname)?;
+
+    let devices = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list;
+    let device = devices
+        .iter()
+        .find(|device| args.mac.matches(&device.mac))
+        .ok_or_else(|| format!("no node with MAC {} in the mesh", args.mac))?;
+
+    let current = device.nickname();
+    if current == args.nickname {
+        eprintln!("{} is already named \"{}\".", args.mac, args.nickname);
+        return Ok(());
+    }
+
+    if dry_run {
+        eprintln!(
+            "dry run mode, would rename {} from \"{}\" to \"{}\"",
+            args.mac, current, args.nickname
+        );
+        return Ok(());
+    }
+
+    api.set_device_nickname(args.mac, &args.nickname)
+        .map_err(|err| format!("failed to rename {}: {}", args.mac, err))?;
+    eprintln!("{} renamed to \"{}\".", args.mac, args.nickname);
+    Ok(())
+}
@@ -0,0 +1,429 @@
+// DHCP address reservations, kept in a JSON or CSV file under version control.
+//
+// `apply` treats the file as the whole truth: reservations that are not in
+// it get removed, missing ones added, and the ones already right are left
+// alone. Changed entries are edited in place, after the removals and in an
+// order that frees an IP before another device takes it, so an IP can move
+// from one device to another in a single run.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+
+#[cfg(test)]
+use crate::mercusys::api_reservation::make_test_reservation;
+use crate::mercusys::api_reservation::Reservation;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+
+#[derive(Args, Debug, Clone)]
+pub struct ReservationsArgs {
+    #[command(subcommand)]
+    pub action: ReservationsAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReservationsAction {
+    /// List the reservations on the router.
+    List,
+
+    /// Reserve an IP for a device, or change its reservation.
+    Add {
+        #[arg(long)]
+        mac: MacAddress,
+
+        #[arg(long)]
+        ip: Ipv4Addr,
+
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+
+    /// Remove the reservation of a device.
+    Remove {
+        #[arg(long)]
+        mac: MacAddress,
+    },
+
+    /// Write the router's reservations to a file, or stdout.
+    Export {
+        /// file to write, .csv or .json.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// format, guessed from --output if omitted, JSON otherwise.
+        #[arg(long, value_parser = ["json", "csv"])]
+        format: Option<String>,
+    },
+
+    /// Make the router's reservations match a .json or .csv file.
+    Apply { file: PathBuf },
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ReservationPlan {
+    pub remove: Vec<Reservation>,
+
+    /// Same MAC, new IP or description. In the order to apply them.
+    pub update: Vec<Reservation>,
+    pub add: Vec<Reservation>,
+}
+
+impl ReservationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_empty() && self.update.is_empty() && self.add.is_empty()
+    }
+}
+
+fn has_mac(reservations: &[Reservation], mac: MacAddress) -> bool {
+    reservations
+        .iter()
+        .any(|reservation| reservation.mac == mac)
+}
+
+/// The smallest set of changes turning `current` into `wanted`.
+pub fn plan_reservations(current: &[Reservation], wanted: &[Reservation]) -> ReservationPlan {
+    let remove: Vec<Reservation> = current
+        .iter()
+        .filter(|reservation| !has_mac(wanted, reservation.mac))
+        .cloned()
+        .collect();
+    let add = wanted
+        .iter()
+        .filter(|reservation| !has_mac(current, reservation.mac))
+        .cloned()
+        .collect();
+
+    // Edit first the ones whose new IP isn't held by another device anymore.
+    //   IPs swapped between devices can't be, the router refuses those.
+    let mut held: Vec<Reservation> = current
+        .iter()
+        .filter(|reservation| !remove.contains(reservation))
+        .cloned()
+        .collect();
+    let mut pending: Vec<Reservation> = wanted
+        .iter()
+        .filter(|reservation| has_mac(current, reservation.mac) && !current.contains(reservation))
+        .cloned()
+        .collect();
+    let mut update = vec![];
+    while !pending.is_empty() {
+        let next = pending
+            .iter()
+            .position(|wanted| {
+                !held
+                    .iter()
+                    .any(|other| other.ip == wanted.ip && other.mac != wanted.mac)
+            })
+            .unwrap_or(0);
+        let reservation = pending.remove(next);
+        for other in held.iter_mut().filter(|other| other.mac == reservation.mac) {
+            *other = reservation.clone();
+        }
+        update.push(reservation);
+    }
+
+    ReservationPlan {
+        remove,
+        update,
+        add,
+    }
+}
+
+fn validate(reservations: &[Reservation]) -> Result<(), String> {
+    let mut macs = HashSet::new();
+    let mut ips = HashSet::new();
+    for reservation in reservations {
+        if !macs.insert(reservation.mac) {
+            return Err(format!("{} is listed more than once", reservation.mac));
+        }
+        if !ips.insert(reservation.ip) {
+            return Err(format!("{} is reserved more than once", reservation.ip));
+        }
+    }
+    Ok(())
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+}
+
+fn load(path: &Path) -> Result<Vec<Reservation>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let invalid = |err: String| format!("failed to parse {}: {}", path.display(), err);
+
+    let reservations: Vec<Reservation> = if is_csv(path) {
+        csv::Reader::from_reader(content.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .map_err(|err| invalid(err.to_string()))?
+    } else {
+        serde_json::from_str(content.as_str()).map_err(|err| invalid(err.to_string()))?
+    };
+
+    validate(&reservations).map_err(invalid)?;
+    Ok(reservations)
+}
+
+fn write_csv<W: io::Write>(out: W, reservations: &[Reservation]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(out);
+    for reservation in reservations {
+        writer
+            .serialize(reservation)
+            .map_err(|err| format!("failed to write CSV: {}", err))?;
+    }
+    writer
+        .flush()
+        .map_err(|err| format!("failed to write CSV: {}", err))
+}
+
+fn export(
+    reservations: &[Reservation],
+    output: Option<&Path>,
+    format: Option<&str>,
+) -> Result<(), String> {
+    let csv = match format {
+        Some(format) => format == "csv",
+        None => output.is_some_and(is_csv),
+    };
+
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path)
+                .map_err(|err| format!("failed to create {}: {}", path.display(), err))?;
+            if csv {
+                write_csv(file, reservations)
+            } else {
+                serde_json::to_writer_pretty(file, reservations)
+                    .map_err(|err| format!("failed to write {}: {}", path.display(), err))
+            }
+        }
+        None if csv => write_csv(io::stdout(), reservations),
+        None => {
+            println!("{}", serde_json::to_string_pretty(reservations).unwrap());
+            Ok(())
+        }
+    }
+}
+
+fn print_reservations(reservations: &[Reservation]) {
+    println!("{:<17}  {:<15}  DESCRIPTION", "MAC", "IP");
+    for reservation in reservations {
+        println!(
+            "{:<17}  {:<15}  {}",
+            reservation.mac, reservation.ip, reservation.description
+        );
+    }
+}
+
+fn apply(
+    api: &mut MercusysHTTP,
+    current: &[Reservation],
+    wanted: &[Reservation],
+    dry_run: bool,
+) -> Result<(), String> {
+    let plan = plan_reservations(current, wanted);
+    if plan.is_empty() {
+        eprintln!("reservations are up to date.");
+        return Ok(());
+    }
+
+    for reservation in &plan.remove {
+        eprintln!(
+            "- {} {} {}",
+            reservation.mac, reservation.ip, reservation.description
+        );
+    }
+    for reservation in &plan.update {
+        eprintln!(
+            "~ {} {} {}",
+            reservation.mac, reservation.ip, reservation.description
+        );
+    }
+    for reservation in &plan.add {
+        eprintln!(
+            "+ {} {} {}",
+            reservation.mac, reservation.ip, reservation.description
+        );
+    }
+    if dry_run {
+        eprintln!("dry run mode, nothing changed.");
+        return Ok(());
+    }
+
+    for reservation in &plan.remove {
+        api.remove_reservation(reservation)
+            .map_err(|err| format!("failed to remove {}: {}", reservation.mac, err))?;
+    }
+    // Edited in place, a failure leaves the old reservation.
+    for reservation in &plan.update {
+        api.update_reservation(reservation)
+            .map_err(|err| format!("failed to update {}: {}", reservation.mac, err))?;
+    }
+    for reservation in &plan.add {
+        api.add_reservation(reservation)
+            .map_err(|err| format!("failed to add {}: {}", reservation.mac, err))?;
+    }
+
+    eprintln!(
+        "{} removed, {} updated, {} added.",
+        plan.remove.len(),
+        plan.update.len(),
+        plan.add.len()
+    );
+    Ok(())
+}
+
+fn add(
+    api: &mut MercusysHTTP,
+    current: &[Reservation],
+    wanted: Reservation,
+    dry_run: bool,
+) -> Result<(), String> {
+    if let Some(other) = current
+        .iter()
+        .find(|other| other.ip == wanted.ip && other.mac != wanted.mac)
+    {
+        return Err(format!(
+            "{} is already reserved for {}",
+            wanted.ip, other.mac
+        ));
+    }
+
+    let existing = current.iter().find(|other| other.mac == wanted.mac);
+    if existing == Some(&wanted) {
+        eprintln!("{} is already reserved for {}.", wanted.ip, wanted.mac);
+        return Ok(());
+    }
+    if dry_run {
+        eprintln!(
+            "dry run mode, would reserve {} for {}",
+            wanted.ip, wanted.mac
+        );
+        return Ok(());
+    }
+
+    let result = match existing {
+        Some(_) => api.update_reservation(&wanted),
+        None => api.add_reservation(&wanted),
+    };
+    result.map_err(|err| format!("failed to reserve {}: {}", wanted.ip, err))?;
+    eprintln!("{} reserved for {}.", wanted.ip, wanted.mac);
+    Ok(())
+}
+
+pub fn run_reservations(
+    api: &mut MercusysHTTP,
+    args: &ReservationsArgs,
+    dry_run: bool,
+) -> Result<(), String> {
+    let mut current = api
+        .get_reservations()
+        .map_err(|err| format!("failed to read reservations: {}", err))?
+        .result
+        .reservations();
+    current.sort();
+
+    match &args.action {
+        ReservationsAction::List => {
+            print_reservations(&current);
+            Ok(())
+        }
+        ReservationsAction::Add {
+            mac,
+            ip,
+            description,
+        } => {
+            let wanted = Reservation {
+                mac: *mac,
+                ip: *ip,
+                description: description.clone(),
+            };
+            add(api, &current, wanted, dry_run)
+        }
+        ReservationsAction::Remove { mac } => {
+            let reservation = current
+                .iter()
+                .find(|reservation| reservation.mac == *mac)
+                .ok_or_else(|| format!("no reservation for {}", mac))?;
+            if dry_run {
+                eprintln!("dry run mode, would remove the reservation of {}", mac);
+                return Ok(());
+            }
+
+            api.remove_reservation(reservation)
+                .map_err(|err| format!("failed to remove {}: {}", mac, err))?;
+            eprintln!("reservation of {} removed.", mac);
+            Ok(())
+        }
+        ReservationsAction::Export { output, format } => {
+            export(&current, output.as_deref(), format.as_deref())
+        }
+        ReservationsAction::Apply { file } => apply(api, &current, &load(file)?, dry_run),
+    }
+}
+
+#[test]
+fn plan_reservations_test() {
+    let current = vec![
+        make_test_reservation("AA-AA-AA-AA-AA-01", "192.168.68.10", "printer"),
+        make_test_reservation("AA-AA-AA-AA-AA-02", "192.168.68.11", "nas"),
+        make_test_reservation("AA-AA-AA-AA-AA-03", "192.168.68.12", "old"),
+    ];
+    let wanted = vec![
+        make_test_reservation("AA-AA-AA-AA-AA-01", "192.168.68.10", "printer"),
+        make_test_reservation("AA-AA-AA-AA-AA-02", "192.168.68.20", "nas"),
+        make_test_reservation("AA-AA-AA-AA-AA-04", "192.168.68.12", "new"),
+    ];
+
+    let plan = plan_reservations(&current, &wanted);
+    assert_eq!(plan.remove, current[2..].to_vec());
+    assert_eq!(plan.update, wanted[1..2].to_vec());
+    assert_eq!(plan.add, wanted[2..].to_vec());
+    assert!(plan_reservations(&wanted, &wanted).is_empty());
+
+    // .11 moves to the printer once the NAS gave it up.
+    let wanted = vec![
+        make_test_reservation("AA-AA-AA-AA-AA-01", "192.168.68.11", "printer"),
+        make_test_reservation("AA-AA-AA-AA-AA-02", "192.168.68.20", "nas"),
+    ];
+    let plan = plan_reservations(&current[..2], &wanted);
+    assert!(plan.remove.is_empty() && plan.add.is_empty());
+    assert_eq!(plan.update, vec![wanted[1].clone(), wanted[0].clone()]);
+
+    assert!(validate(&wanted).is_ok());
+    let duplicate = vec![
+        make_test_reservation("AA-AA-AA-AA-AA-01", "192.168.68.10", ""),
+        make_test_reservation("AA-AA-AA-AA-AA-02", "192.168.68.10", ""),
+    ];
+    assert!(validate(&duplicate).is_err());
+}
+
+#[test]
+fn reservations_csv_test() {
+    let reservations = vec![make_test_reservation(
+        "aa:bb:cc:dd:ee:ff",
+        "192.168.68.10",
+        "printer, upstairs",
+    )];
+
+    let mut out = vec![];
+    write_csv(&mut out, &reservations).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(
+        text,
+        "mac,ip,description\nAA-BB-CC-DD-EE-FF,192.168.68.10,\"printer, upstairs\"\n"
+    );
+
+    let parsed: Vec<Reservation> = csv::Reader::from_reader(text.as_bytes())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(parsed, reservations);
+}
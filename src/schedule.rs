@@ -0,0 +1,126 @@
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_reboot::{RebootSchedule, WEEKDAYS};
+use crate::mercusys::http::MercusysHTTP;
+use crate::util::parse_time;
+
+/// Same order as `WEEKDAYS`.
+const DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+#[derive(Args, Debug, Clone)]
+pub struct ScheduleArgs {
+    #[command(subcommand)]
+    pub action: ScheduleAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ScheduleAction {
+    /// Show the router's own reboot schedule.
+    Show,
+
+    /// Enable the reboot schedule.
+    Set {
+        /// router local time, e.g. 03:30.
+        #[arg(long, value_parser = parse_time)]
+        time: String,
+
+        /// "daily", "weekdays", "weekends" or a list such as mon,thu.
+        #[arg(long, default_value = "daily")]
+        days: String,
+    },
+
+    /// Disable the reboot schedule.
+    Disable,
+}
+
+fn parse_days(value: &str) -> Result<Vec<String>, String> {
+    let days: Vec<&str> = match value.trim().to_lowercase().as_str() {
+        "daily" => WEEKDAYS.to_vec(),
+        "weekdays" => WEEKDAYS[..5].to_vec(),
+        "weekends" => WEEKDAYS[5..].to_vec(),
+        list => {
+            let mut days = vec![];
+            for day in list.split(',') {
+                // Full names or 3 letter abbreviations only, "monkey" is no day.
+                let day = WEEKDAYS
+                    .iter()
+                    .zip(DAY_NAMES)
+                    .find(|(short, long)| [**short, *long].contains(&day.trim()))
+                    .map(|(short, _)| short)
+                    .ok_or_else(|| format!("unknown day \"{}\"", day))?;
+                if !days.contains(day) {
+                    days.push(*day);
+                }
+            }
+            days
+        }
+    };
+
+    Ok(days.into_iter().map(String::from).collect())
+}
+
+fn print_schedule(schedule: &RebootSchedule) {
+    if schedule.enable {
+        println!("enabled: {} at {}", schedule.days.join(","), schedule.time);
+    } else {
+        println!("disabled");
+    }
+}
+
+pub fn run_schedule(
+    api: &mut MercusysHTTP,
+    args: &ScheduleArgs,
+    dry_run: bool,
+) -> Result<(), String> {
+    let mut schedule = api
+        .get_reboot_schedule()
+        .map_err(|err| format!("failed to read reboot schedule: {}", err))?
+        .result;
+
+    match &args.action {
+        ScheduleAction::Show => {
+            print_schedule(&schedule);
+            return Ok(());
+        }
+        ScheduleAction::Set { time, days } => {
+            schedule.enable = true;
+            schedule.time = time.clone();
+            schedule.days = parse_days(days)?;
+        }
+        ScheduleAction::Disable => schedule.enable = false,
+    }
+    schedule.validate()?;
+
+    if dry_run {
+        eprintln!("dry run mode, would write:");
+        print_schedule(&schedule);
+        return Ok(());
+    }
+
+    api.set_reboot_schedule(&schedule)
+        .map_err(|err| format!("failed to write reboot schedule: {}", err))?;
+    print_schedule(&schedule);
+    Ok(())
+}
+
+#[test]
+fn parse_days_test() {
+    assert_eq!(parse_days("weekends"), Ok(vec!["sat".into(), "sun".into()]));
+    assert_eq!(
+        parse_days("Monday,thu,mon"),
+        Ok(vec!["mon".into(), "thu".into()])
+    );
+    assert_eq!(parse_days("daily").unwrap().len(), 7);
+    assert!(parse_days("someday").is_err());
+    assert!(parse_days("monkey").is_err());
+    assert!(parse_days("sunny").is_err());
+    assert!(parse_days("tues").is_err());
+}
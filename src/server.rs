@@ -0,0 +1,220 @@
+// Local REST API, so several machines can control the router while only this
+// process holds the admin session.
+//
+//   GET  /health        no auth, is the server up and logged in
+//   GET  /devices       device list, as returned by the router
+//   POST /reboot        reboot the whole mesh
+//   POST /reboot/{mac}  reboot a single node, e.g. aa:bb:cc:dd:ee:ff
+//
+// Everything but `/health` requires `Authorization: Bearer <token>`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use clap::Args;
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::mercusys::error::MercusysError;
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub listen: String,
+
+    /// bearer token clients have to send.
+    #[arg(long, value_parser = parse_token)]
+    pub token: String,
+
+    /// number of worker threads.
+    #[arg(long, default_value_t = 4)]
+    pub workers: usize,
+}
+
+fn parse_token(s: &str) -> Result<String, String> {
+    if s.trim().is_empty() {
+        return Err("token must not be empty".into());
+    }
+    Ok(s.into())
+}
+
+struct ApiResponse {
+    status: u16,
+    body: String,
+}
+
+impl ApiResponse {
+    fn json<T: Serialize>(status: u16, body: &T) -> ApiResponse {
+        ApiResponse {
+            status,
+            body: serde_json::to_string(body).unwrap(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> ApiResponse {
+        ApiResponse::json(status, &json!({"success": false, "error": message}))
+    }
+
+    fn router_error(err: MercusysError) -> ApiResponse {
+        ApiResponse::error(502, err.to_string().as_str())
+    }
+}
+
+struct Handler {
+    api: Arc<Mutex<MercusysHTTP>>,
+    token: String,
+    dry_run: bool,
+
+    /// Kept outside the mutex, so `/health` answers during a slow router call.
+    logged_in: AtomicBool,
+}
+
+/// Compare without bailing out at the first mismatch, so response times
+///   don't leak how much of the token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl Handler {
+    fn api(&self) -> MutexGuard<'_, MercusysHTTP> {
+        // A panicking request should not take the whole server down.
+        self.api.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn update_logged_in(&self, api: &MercusysHTTP) {
+        self.logged_in
+            .store(!api.stok.is_empty(), Ordering::Relaxed);
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let expected = format!("Bearer {}", self.token);
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| constant_time_eq(header.value.as_str().as_bytes(), expected.as_bytes()))
+            .unwrap_or(false)
+    }
+
+    fn reboot(&self, mac: Option<MacAddress>) -> ApiResponse {
+        let mut api = self.api();
+
+        let devices = api.with_relogin(|api| api.get_device_list());
+        self.update_logged_in(&api);
+        let devices = match devices {
+            Ok(devices) => devices.result.device_list,
+            Err(err) => return ApiResponse::router_error(err),
+        };
+
+        let macs: Vec<String> = match mac {
+            None => devices.into_iter().map(|device| device.mac).collect(),
+            Some(mac) => match devices.into_iter().find(|device| mac.matches(&device.mac)) {
+                Some(device) => vec![device.mac],
+                None => return ApiResponse::error(404, "no such device"),
+            },
+        };
+
+        if self.dry_run {
+            return ApiResponse::json(
+                200,
+                &json!({"success": true, "dry_run": true, "mac_list": macs}),
+            );
+        }
+
+        // The session was just refreshed above. Not retried, a reboot that
+        //   went through but timed out would run twice.
+        match api.reboot_devices(&macs) {
+            Ok(resp) => ApiResponse::json(200, &resp),
+            Err(err) => ApiResponse::router_error(err),
+        }
+    }
+
+    fn handle(&self, request: &Request) -> ApiResponse {
+        let path = request.url().split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        if let (Method::Get, ["health"]) = (request.method(), segments.as_slice()) {
+            let logged_in = self.logged_in.load(Ordering::Relaxed);
+            return ApiResponse::json(200, &json!({"success": true, "logged_in": logged_in}));
+        }
+
+        if !self.is_authorized(request) {
+            return ApiResponse::error(401, "unauthorized");
+        }
+
+        match (request.method(), segments.as_slice()) {
+            (Method::Get, ["devices"]) => {
+                let mut api = self.api();
+                let devices = api.with_relogin(|api| api.get_device_list());
+                self.update_logged_in(&api);
+                match devices {
+                    Ok(devices) => ApiResponse::json(200, &devices),
+                    Err(err) => ApiResponse::router_error(err),
+                }
+            }
+            (Method::Post, ["reboot"]) => self.reboot(None),
+            (Method::Post, ["reboot", mac]) => match mac.parse() {
+                Ok(mac) => self.reboot(Some(mac)),
+                Err(err) => ApiResponse::error(400, &err),
+            },
+            _ => ApiResponse::error(404, "not found"),
+        }
+    }
+}
+
+pub fn serve(api: MercusysHTTP, args: &ServeArgs, dry_run: bool) -> Result<(), String> {
+    let server = Server::http(args.listen.as_str())
+        .map_err(|err| format!("failed to listen on {}: {}", args.listen, err))?;
+    let server = Arc::new(server);
+    let handler = Arc::new(Handler {
+        logged_in: AtomicBool::new(!api.stok.is_empty()),
+        api: Arc::new(Mutex::new(api)),
+        token: args.token.clone(),
+        dry_run,
+    });
+
+    eprintln!("listening on http://{}", args.listen);
+
+    let workers: Vec<_> = (0..args.workers.max(1))
+        .map(|_| {
+            let server = server.clone();
+            let handler = handler.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let resp = handler.handle(&request);
+                    let content_type =
+                        Header::from_bytes("Content-Type", "application/json").unwrap();
+                    let resp = Response::from_string(resp.body)
+                        .with_status_code(resp.status)
+                        .with_header(content_type);
+
+                    if let Err(err) = request.respond(resp) {
+                        eprintln!("failed to send response: {}", err);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    Ok(())
+}
+
+#[test]
+fn constant_time_eq_test() {
+    assert!(constant_time_eq(b"Bearer abc", b"Bearer abc"));
+    assert!(!constant_time_eq(b"Bearer abd", b"Bearer abc"));
+    assert!(!constant_time_eq(b"Bearer ab", b"Bearer abc"));
+    assert!(!constant_time_eq(b"", b"Bearer abc"));
+}
@@ -0,0 +1,109 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+
+use crate::mercusys::api_speedtest::SpeedTestResult;
+use crate::mercusys::http::MercusysHTTP;
+
+#[derive(Args, Debug, Clone)]
+pub struct SpeedtestArgs {
+    /// seconds to wait for the test to finish.
+    #[arg(long, default_value_t = 120)]
+    pub timeout: u64,
+
+    /// print the raw JSON instead.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+/// The test we started is done. Right after starting, the router may still
+///   report "idle" or the previous result, those don't count.
+fn is_new_result(result: &SpeedTestResult, previous_test_time: i64) -> bool {
+    result.is_done() && result.test_time > previous_test_time
+}
+
+/// Start a speed test on the main unit and wait for its result.
+fn measure_speed(api: &mut MercusysHTTP, timeout: Duration) -> Result<SpeedTestResult, String> {
+    let devices = api
+        .get_device_list()
+        .map_err(|err| format!("failed to read device list: {}", err))?
+        .result
+        .device_list;
+    if let Some(main) = devices.iter().find(|device| device.is_main()) {
+        if main.speed_get_support == Some(false) {
+            return Err(format!(
+                "{} does not support speed tests",
+                main.device_model
+            ));
+        }
+    }
+
+    let previous_test_time = api
+        .get_speed_test()
+        .map_err(|err| format!("failed to read speed test: {}", err))?
+        .result
+        .test_time;
+    api.start_speed_test()
+        .map_err(|err| format!("failed to start speed test: {}", err))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut seen_running = false;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(2));
+
+        let result = api
+            .get_speed_test()
+            .map_err(|err| format!("failed to read speed test: {}", err))?
+            .result;
+        if is_new_result(&result, previous_test_time) {
+            return Ok(result);
+        }
+        if result.is_running() {
+            seen_running = true;
+            continue;
+        }
+        // Not picked up yet.
+        if !seen_running {
+            continue;
+        }
+        return Err(format!(
+            "speed test ended without a result ({})",
+            result.status
+        ));
+    }
+
+    Err(format!(
+        "speed test did not finish within {}s",
+        timeout.as_secs()
+    ))
+}
+
+pub fn run_speedtest(api: &mut MercusysHTTP, args: &SpeedtestArgs) -> Result<(), String> {
+    eprintln!("running speed test...");
+    let result = measure_speed(api, Duration::from_secs(args.timeout))?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else {
+        println!("download: {:.1} Mbps", result.download_mbps());
+        println!("upload:   {:.1} Mbps", result.upload_mbps());
+        println!("latency:  {} ms", result.latency);
+    }
+    Ok(())
+}
+
+#[test]
+fn is_new_result_test() {
+    let result = |status: &str, test_time: i64| SpeedTestResult {
+        status: status.into(),
+        test_time,
+        ..Default::default()
+    };
+
+    assert!(is_new_result(&result("done", 1700000100), 1700000000));
+    // The previous test, not ours.
+    assert!(!is_new_result(&result("done", 1700000000), 1700000000));
+    assert!(!is_new_result(&result("idle", 0), 0));
+    assert!(!is_new_result(&result("testing", 1700000000), 1700000000));
+}
@@ -0,0 +1,207 @@
+// One-off tasks to be run later by the daemon, e.g. turning the guest network
+// off again after a few hours, or unblocking a client.
+//
+// Commands append to a small JSON file in the user's state directory, the
+// daemon polls it and runs whatever is due. Every read-modify-write holds an
+// exclusive lock on a `.lock` file next to it, so a command and the daemon
+// can't drop each other's changes. The file itself is replaced through a
+// private temp file and a rename, so readers never see a half written queue.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mercusys::http::MercusysHTTP;
+use crate::mercusys::mac_address::MacAddress;
+use crate::util::{create_private_dir, write_private_file};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "task", rename_all = "snake_case")]
+pub enum Task {
+    /// Disable the guest network.
+    GuestOff,
+
+    /// Give a blocked client access again.
+    Unblock { mac: MacAddress },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingTask {
+    /// Unix timestamp (seconds).
+    pub due: u64,
+
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+/// `$XDG_STATE_HOME/mercusys-reboot/tasks.json`, `~/.local/state/...` by default.
+pub fn default_tasks_file() -> PathBuf {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty());
+
+    let state_dir = env_dir("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env_dir("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .or_else(|| env_dir("LOCALAPPDATA").map(PathBuf::from))
+        .unwrap_or_default();
+
+    state_dir.join("mercusys-reboot").join("tasks.json")
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Unix time `duration` from now, for `TaskQueue::push`.
+pub fn due_in(duration: Duration) -> Result<u64, String> {
+    unix_now()
+        .checked_add(duration.as_secs())
+        .ok_or_else(|| format!("{}s is too far in the future", duration.as_secs()))
+}
+
+pub struct TaskQueue {
+    path: PathBuf,
+}
+
+impl TaskQueue {
+    pub fn new<P: AsRef<Path>>(path: P) -> TaskQueue {
+        TaskQueue {
+            path: path.as_ref().into(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Held until the returned file is dropped.
+    fn lock(&self) -> Result<File, String> {
+        let err = |err: std::io::Error| format!("failed to lock {}: {}", self.path.display(), err);
+
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_private_dir(dir).map_err(err)?;
+        }
+
+        let mut lock_path = OsString::from(&self.path);
+        lock_path.push(".lock");
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let file = options.open(lock_path).map_err(err)?;
+        file.lock().map_err(err)?;
+        Ok(file)
+    }
+
+    pub fn load(&self) -> Result<Vec<PendingTask>, String> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(format!("failed to read {}: {}", self.path.display(), err)),
+        };
+
+        serde_json::from_str(content.as_str())
+            .map_err(|err| format!("failed to parse {}: {}", self.path.display(), err))
+    }
+
+    fn save(&self, tasks: &[PendingTask]) -> Result<(), String> {
+        write_private_file(&self.path, &serde_json::to_string_pretty(tasks).unwrap())
+            .map_err(|err| format!("failed to write {}: {}", self.path.display(), err))
+    }
+
+    /// Queue `task`, replacing an identical one that is already pending.
+    pub fn push(&self, task: Task, due: u64) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut tasks = self.load()?;
+        tasks.retain(|pending| pending.task != task);
+        tasks.push(PendingTask { due, task });
+        self.save(&tasks)
+    }
+
+    /// Drop a pending task, e.g. because it was done by hand.
+    pub fn cancel(&self, task: &Task) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut tasks = self.load()?;
+        let count = tasks.len();
+        tasks.retain(|pending| &pending.task != task);
+        if tasks.len() != count {
+            self.save(&tasks)?;
+        }
+        Ok(())
+    }
+
+    /// Remove and return every task due by `now`.
+    pub fn take_due(&self, now: u64) -> Result<Vec<PendingTask>, String> {
+        let _lock = self.lock()?;
+        let tasks = self.load()?;
+        let (due, pending): (Vec<PendingTask>, Vec<PendingTask>) =
+            tasks.into_iter().partition(|task| task.due <= now);
+        if !due.is_empty() {
+            self.save(&pending)?;
+        }
+        Ok(due)
+    }
+}
+
+pub fn run_task(api: &mut MercusysHTTP, task: &Task) -> Result<(), String> {
+    match task {
+        Task::GuestOff => api
+            .with_relogin(|api| api.set_guest_enabled(false))
+            .map(|_| ())
+            .map_err(|err| format!("failed to disable guest network: {}", err)),
+        Task::Unblock { mac } => api
+            .with_relogin(|api| api.unblock_client(*mac, ""))
+            .map(|_| ())
+            .map_err(|err| format!("failed to unblock {}: {}", mac, err)),
+    }
+}
+
+#[test]
+fn task_queue_test() {
+    let dir = std::env::temp_dir().join(format!("mercusys-reboot-test-{}", std::process::id()));
+    let queue = TaskQueue::new(dir.join("tasks.json"));
+
+    queue.push(Task::GuestOff, 100).unwrap();
+    queue.push(Task::GuestOff, 200).unwrap();
+    let mac: MacAddress = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+    queue.push(Task::Unblock { mac }, 50).unwrap();
+    assert_eq!(queue.load().unwrap().len(), 2);
+
+    let due = queue.take_due(150).unwrap();
+    assert_eq!(
+        due,
+        vec![PendingTask {
+            due: 50,
+            task: Task::Unblock { mac }
+        }]
+    );
+
+    queue.cancel(&Task::GuestOff).unwrap();
+    assert!(queue.load().unwrap().is_empty());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(queue.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn due_in_test() {
+    let now = unix_now();
+    assert!(due_in(Duration::from_secs(60)).unwrap() >= now + 60);
+    assert!(due_in(Duration::from_secs(u64::MAX)).is_err());
+}
@@ -0,0 +1,167 @@
+// Small helpers shared by several commands.
+
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveTime;
+
+/// Seconds as "H:MM:SS".
+pub fn format_duration(secs: i64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// "90", "90s", "15m", "4h", "2d" or combined, e.g. "1h30m".
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid duration \"{}\", expected e.g. 90s, 15m, 4h or 1h30m",
+            value
+        )
+    };
+
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let secs = number.parse::<u64>().ok().and_then(|n| n.checked_mul(unit));
+        total = secs
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        let secs = number.parse::<u64>().ok();
+        total = secs
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+    }
+    if total == 0 {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+/// "7:30" or "07:30", normalized to "07:30".
+pub fn parse_time(value: &str) -> Result<String, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map(|time| time.format("%H:%M").to_string())
+        .map_err(|_| format!("invalid time \"{}\", expected HH:MM", value))
+}
+
+/// "22:00-07:00", normalized to ("22:00", "07:00"). May span midnight.
+pub fn parse_time_range(value: &str) -> Result<(String, String), String> {
+    let invalid = || {
+        format!(
+            "invalid time range \"{}\", expected e.g. 22:00-07:00",
+            value
+        )
+    };
+
+    let (start, end) = value.trim().split_once('-').ok_or_else(invalid)?;
+    let start = parse_time(start).map_err(|_| invalid())?;
+    let end = parse_time(end).map_err(|_| invalid())?;
+    if start == end {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+/// Open a file for writing that only the current user can read, failing if
+///   it already exists.
+pub fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+/// Create `dir` and its parents, the last one only accessible by the current user.
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(dir)
+}
+
+/// Replace the content of `path` through a private temp file next to it and a
+///   rename, so readers never see a half written file.
+pub fn write_private_file(path: &Path, content: &str) -> io::Result<()> {
+    let mut tmp_path = OsString::from(path);
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    // Left over by a crash, start over.
+    match fs::remove_file(&tmp_path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+
+    let mut file = create_private_file(&tmp_path)?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
+}
+
+#[test]
+fn parse_duration_test() {
+    assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(15 * 60)));
+    assert_eq!(parse_duration("4h"), Ok(Duration::from_secs(4 * 3600)));
+    assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+    assert!(parse_duration("4x").is_err());
+    assert!(parse_duration("h").is_err());
+    assert!(parse_duration("0s").is_err());
+    assert!(parse_duration("99999999999999999d").is_err());
+    assert!(parse_duration("18446744073709551615s1s").is_err());
+}
+
+#[test]
+fn parse_time_range_test() {
+    assert_eq!(
+        parse_time_range("22:00-7:30"),
+        Ok(("22:00".into(), "07:30".into()))
+    );
+    assert_eq!(parse_time(" 7:30"), Ok("07:30".into()));
+    assert!(parse_time("7:30pm").is_err());
+    assert!(parse_time_range("22:00").is_err());
+    assert!(parse_time_range("25:00-07:00").is_err());
+    assert!(parse_time_range("07:00-07:00").is_err());
+}
+
+#[test]
+fn format_duration_test() {
+    assert_eq!(format_duration(59), "0:00:59");
+    assert_eq!(format_duration(3 * 3600 + 61), "3:01:01");
+    assert_eq!(format_duration(30 * 3600), "30:00:00");
+}
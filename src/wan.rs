@@ -0,0 +1,132 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_wan::WanStatus;
+use crate::mercusys::http::MercusysHTTP;
+use crate::util::format_duration;
+
+#[derive(Args, Debug, Clone)]
+pub struct WanArgs {
+    #[command(subcommand)]
+    pub action: WanAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WanAction {
+    /// Show the internet connection of the main unit.
+    Status {
+        /// print the raw JSON instead.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Renew the DHCP lease or redial PPPoE, without rebooting the mesh.
+    Reconnect {
+        /// seconds to wait for the connection to come back.
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+}
+
+fn print_status(status: &WanStatus) {
+    let link = if status.link_speed > 0 {
+        format!("{} Mbps {}", status.link_speed, status.link_duplex)
+    } else {
+        "down".into()
+    };
+
+    println!("status:   {}", status.link_status);
+    println!("type:     {}", status.connection_type);
+    println!("ip:       {}/{}", status.ip_address, status.netmask);
+    println!("gateway:  {}", status.gateway);
+    println!("dns:      {} {}", status.dns1, status.dns2);
+    println!("uptime:   {}", format_duration(status.uptime));
+    println!("link:     {}", link);
+}
+
+/// Right after the request the WAN still reports the old connection, so only
+///   count it as back once it was seen down, or its uptime started over.
+fn has_reconnected(before: &WanStatus, went_down: bool, status: &WanStatus) -> bool {
+    status.is_connected() && (went_down || status.uptime < before.uptime)
+}
+
+/// Reconnect, then wait until the WAN is connected again.
+pub fn reconnect(api: &mut MercusysHTTP, timeout: Duration) -> Result<WanStatus, String> {
+    let before = api
+        .get_wan_status()
+        .map_err(|err| format!("failed to read WAN status: {}", err))?
+        .result;
+    let resp = api
+        .reconnect_wan()
+        .map_err(|err| format!("failed to reconnect WAN: {}", err))?;
+    eprintln!(
+        "reconnecting, router expects it to take {}s",
+        resp.result.wait_time
+    );
+
+    let deadline = Instant::now() + timeout;
+    let mut went_down = false;
+    while Instant::now() < deadline {
+        sleep(Duration::from_secs(2));
+        match api.get_wan_status() {
+            Ok(status) if has_reconnected(&before, went_down, &status.result) => {
+                return Ok(status.result)
+            }
+            Ok(status) => went_down |= !status.result.is_connected(),
+            Err(err) => eprintln!("failed to read WAN status: {}", err),
+        }
+    }
+
+    Err(format!(
+        "WAN did not come back within {}s",
+        timeout.as_secs()
+    ))
+}
+
+pub fn run_wan(api: &mut MercusysHTTP, args: &WanArgs, dry_run: bool) -> Result<(), String> {
+    match &args.action {
+        WanAction::Status { json } => {
+            let status = api
+                .get_wan_status()
+                .map_err(|err| format!("failed to read WAN status: {}", err))?
+                .result;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&status).unwrap());
+            } else {
+                print_status(&status);
+            }
+            Ok(())
+        }
+        WanAction::Reconnect { timeout } => {
+            if dry_run {
+                eprintln!("dry run mode, would reconnect the WAN.");
+                return Ok(());
+            }
+
+            let status = reconnect(api, Duration::from_secs(*timeout))?;
+            print_status(&status);
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn has_reconnected_test() {
+    let status = |link_status: &str, uptime: i64| WanStatus {
+        link_status: link_status.into(),
+        uptime,
+        ..Default::default()
+    };
+    let before = status("connected", 3600);
+
+    // Not reconnected yet, still the old connection.
+    assert!(!has_reconnected(&before, false, &status("connected", 3604)));
+    assert!(!has_reconnected(&before, false, &status("connecting", 0)));
+    // Back after being seen down.
+    assert!(has_reconnected(&before, true, &status("connected", 3610)));
+    assert!(!has_reconnected(&before, true, &status("disconnected", 0)));
+    // Came back between two polls.
+    assert!(has_reconnected(&before, false, &status("connected", 3)));
+}
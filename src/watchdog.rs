@@ -0,0 +1,398 @@
+// Internet watchdog: only reboot the mesh when the internet is actually gone.
+//
+// Every interval, the configured targets are probed (and/or the main unit's
+// `inet_status` is read). A single working probe means we are online. After
+// enough consecutive failures the whole mesh gets rebooted, followed by a
+// cool-down, and never more than a few times a day. With `--wan-reconnect`,
+// the WAN connection is renewed first and the reboot only comes after another
+// round of failures. With `--router-ping`, the router pings a host itself
+// before rebooting: if it can't reach it either, the outage is the ISP's and
+// rebooting the mesh would not help, so the reboot is skipped.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use clap::{Args, FromArgMatches};
+use serde::Deserialize;
+
+use crate::diag::router_ping;
+use crate::mercusys::api_diagnostic::PingResult;
+use crate::mercusys::http::MercusysHTTP;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+const ROUTER_PING_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Probe {
+    /// `tcp:host:port`, connect and close.
+    Tcp(String),
+
+    /// `http://...` or `https://...`, any non-error response.
+    Http(String),
+
+    /// `dns:hostname`, resolved with the system resolver.
+    Dns(String),
+}
+
+impl FromStr for Probe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(Probe::Http(s.into()));
+        }
+
+        match s.split_once(':') {
+            Some(("tcp", addr)) if addr.contains(':') => Ok(Probe::Tcp(addr.into())),
+            Some(("dns", host)) if !host.is_empty() => Ok(Probe::Dns(host.into())),
+            _ => Err(format!(
+                "invalid target \"{}\", expected tcp:HOST:PORT, dns:HOST or http(s)://URL",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Probe {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Probe::Tcp(addr) => write!(f, "tcp:{}", addr),
+            Probe::Http(url) => write!(f, "{}", url),
+            Probe::Dns(host) => write!(f, "dns:{}", host),
+        }
+    }
+}
+
+impl Probe {
+    pub fn check(&self, timeout: Duration) -> bool {
+        match self {
+            Probe::Tcp(addr) => match addr.to_socket_addrs() {
+                Ok(mut addrs) => {
+                    addrs.any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok())
+                }
+                Err(_) => false,
+            },
+            Probe::Http(url) => reqwest::blocking::Client::builder()
+                .timeout(timeout)
+                .build()
+                .and_then(|client| client.get(url).send())
+                .map(|resp| resp.status().is_success() || resp.status().is_redirection())
+                .unwrap_or(false),
+            Probe::Dns(host) => match (host.as_str(), 0).to_socket_addrs() {
+                Ok(mut addrs) => addrs.next().is_some(),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+/// Also used as `watch` profile in the daemon config,
+///   where omitted fields take the command line defaults.
+#[derive(Args, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchArgs {
+    /// probe target, repeatable: tcp:HOST:PORT, dns:HOST or http(s)://URL.
+    #[arg(short, long = "target")]
+    pub targets: Vec<Probe>,
+
+    /// check `inet_status` of the main unit (default if no target is given).
+    #[arg(long, default_value_t = false)]
+    pub device_status: bool,
+
+    /// seconds between checks.
+    #[arg(long, default_value_t = 60)]
+    pub interval: u64,
+
+    /// seconds before a single probe gives up.
+    #[arg(long, default_value_t = 5)]
+    pub probe_timeout: u64,
+
+    /// consecutive failed checks before rebooting.
+    #[arg(long, default_value_t = 3)]
+    pub failures: u32,
+
+    /// seconds to wait after a reboot before checking again.
+    #[arg(long, default_value_t = 900)]
+    pub cooldown: u64,
+
+    /// maximum number of reboots within 24 hours.
+    #[arg(long, default_value_t = 3)]
+    pub max_reboots_per_day: usize,
+
+    /// try reconnecting the WAN (DHCP renew, PPPoE redial) once before rebooting.
+    #[arg(long, default_value_t = false)]
+    pub wan_reconnect: bool,
+
+    /// before rebooting, ping this host from the router. Unreachable from there
+    ///   too means an ISP outage, and no reboot.
+    #[arg(long)]
+    pub router_ping: Option<String>,
+}
+
+impl Default for WatchArgs {
+    fn default() -> Self {
+        let command = WatchArgs::augment_args(clap::Command::new("watch"));
+        WatchArgs::from_arg_matches(&command.get_matches_from(["watch"])).unwrap()
+    }
+}
+
+/// An unreachable host from the router means an ISP outage. A failed ping
+///   proves nothing, so it doesn't count as one.
+fn ping_shows_isp_outage(host: &str, ping: Result<PingResult, String>) -> bool {
+    match ping {
+        Ok(result) if result.is_reachable() => {
+            eprintln!(
+                "watchdog: router reaches {} ({:.0}% loss), problem is on the LAN side",
+                host,
+                result.loss_percent()
+            );
+            false
+        }
+        Ok(_) => {
+            eprintln!("watchdog: router can't reach {} either, ISP outage", host);
+            true
+        }
+        Err(err) => {
+            eprintln!("watchdog: router ping failed: {}", err);
+            false
+        }
+    }
+}
+
+pub struct Watchdog {
+    args: WatchArgs,
+    dry_run: bool,
+
+    consecutive_failures: u32,
+    cooldown_until: Option<Instant>,
+    reboots: VecDeque<Instant>,
+
+    /// WAN already reconnected during the current outage.
+    wan_reconnected: bool,
+}
+
+impl Watchdog {
+    pub fn new(args: WatchArgs, dry_run: bool) -> Watchdog {
+        Watchdog {
+            args,
+            dry_run,
+            consecutive_failures: 0,
+            cooldown_until: None,
+            reboots: VecDeque::new(),
+            wan_reconnected: false,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.args.interval)
+    }
+
+    fn check_device_status(&self, api: &mut MercusysHTTP) -> bool {
+        match api.with_relogin(|api| api.get_device_list()) {
+            Ok(devices) => devices
+                .result
+                .device_list
+                .iter()
+                .any(|device| device.is_main() && device.is_online()),
+            Err(err) => {
+                eprintln!("watchdog: failed to read device list: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Whether the router itself can't reach the internet either.
+    fn is_isp_outage(&self, api: &mut MercusysHTTP, host: &str) -> bool {
+        ping_shows_isp_outage(host, router_ping(api, host, 3, ROUTER_PING_TIMEOUT))
+    }
+
+    fn is_connected(&self, api: &mut MercusysHTTP) -> bool {
+        let timeout = Duration::from_secs(self.args.probe_timeout);
+        for probe in self.args.targets.iter() {
+            if probe.check(timeout) {
+                return true;
+            }
+            eprintln!("watchdog: probe {} failed", probe);
+        }
+
+        if self.args.device_status || self.args.targets.is_empty() {
+            return self.check_device_status(api);
+        }
+
+        false
+    }
+
+    /// Run one round of checks, and reboot if it's time to.
+    pub fn tick(&mut self, api: &mut MercusysHTTP) {
+        if let Some(until) = self.cooldown_until {
+            if Instant::now() < until {
+                return;
+            }
+            self.cooldown_until = None;
+        }
+
+        if self.is_connected(api) {
+            if self.consecutive_failures > 0 {
+                eprintln!("watchdog: connectivity restored");
+            }
+            self.consecutive_failures = 0;
+            self.wan_reconnected = false;
+            return;
+        }
+
+        self.consecutive_failures += 1;
+        eprintln!(
+            "watchdog: connectivity check failed ({}/{})",
+            self.consecutive_failures, self.args.failures
+        );
+        if self.consecutive_failures < self.args.failures {
+            return;
+        }
+
+        // Cheaper than a reboot, give it another round of checks.
+        if self.args.wan_reconnect && !self.wan_reconnected {
+            self.wan_reconnected = true;
+            self.consecutive_failures = 0;
+
+            if self.dry_run {
+                eprintln!("watchdog: dry run mode, not reconnecting the WAN.");
+                return;
+            }
+            // Not retried, like the reboot below.
+            match api.ensure_session().and_then(|_| api.reconnect_wan()) {
+                Ok(_) => {
+                    eprintln!("watchdog: WAN reconnect requested, rebooting if it doesn't help");
+                    return;
+                }
+                Err(err) => eprintln!("watchdog: WAN reconnect failed: {}", err),
+            }
+        }
+
+        if let Some(host) = self.args.router_ping.clone() {
+            if self.is_isp_outage(api, &host) {
+                eprintln!("watchdog: rebooting the mesh won't help, skip.");
+                self.consecutive_failures = 0;
+                return;
+            }
+        }
+
+        while let Some(at) = self.reboots.front() {
+            if at.elapsed() < DAY {
+                break;
+            }
+            self.reboots.pop_front();
+        }
+        if self.reboots.len() >= self.args.max_reboots_per_day {
+            eprintln!(
+                "watchdog: already rebooted {} times in the last 24 hours, skip.",
+                self.reboots.len()
+            );
+            return;
+        }
+
+        if self.dry_run {
+            eprintln!("watchdog: dry run mode, not rebooting.");
+        } else {
+            // Not retried, a reboot that went through but timed out would run twice.
+            match api.ensure_session().and_then(|_| api.reboot_whole_mesh()) {
+                Ok(resp) => eprintln!(
+                    "watchdog: reboot success with wait timeout of {}",
+                    resp.result.reboot_time
+                ),
+                Err(err) => {
+                    eprintln!("watchdog: reboot failed: {}", err);
+                    return;
+                }
+            }
+        }
+
+        self.consecutive_failures = 0;
+        self.wan_reconnected = false;
+        self.reboots.push_back(Instant::now());
+        self.cooldown_until = Some(Instant::now() + Duration::from_secs(self.args.cooldown));
+    }
+
+    pub fn run(&mut self, api: &mut MercusysHTTP) {
+        loop {
+            self.tick(api);
+            sleep(self.interval());
+        }
+    }
+}
+
+#[test]
+fn probe_parse_test() {
+    assert_eq!(
+        "tcp:1.1.1.1:53".parse(),
+        Ok(Probe::Tcp("1.1.1.1:53".into()))
+    );
+    assert_eq!(
+        "dns:example.com".parse(),
+        Ok(Probe::Dns("example.com".into()))
+    );
+    assert_eq!(
+        "https://example.com/".parse(),
+        Ok(Probe::Http("https://example.com/".into()))
+    );
+    assert!("tcp:1.1.1.1".parse::<Probe>().is_err());
+    assert!("ping:1.1.1.1".parse::<Probe>().is_err());
+}
+
+#[test]
+fn is_isp_outage_test() {
+    let ping = |received: u32| PingResult {
+        transmitted: 3,
+        received,
+        ..Default::default()
+    };
+
+    assert!(ping_shows_isp_outage("1.1.1.1", Ok(ping(0))));
+    assert!(!ping_shows_isp_outage("1.1.1.1", Ok(ping(1))));
+    assert!(!ping_shows_isp_outage(
+        "1.1.1.1",
+        Err("ping timed out".into())
+    ));
+}
+
+#[test]
+fn is_isp_outage_relogin_test() {
+    use crate::mercusys::http::spawn_fake_router;
+    use serde_json::json;
+
+    let mut started = false;
+    let url = spawn_fake_router(
+        move |form, request| match (form, request["operation"].as_str()) {
+            ("ping", Some("start")) => {
+                started = true;
+                json!({})
+            }
+            ("ping", _) if started => json!({"result": {
+                "status": "done",
+                "output": "3 packets transmitted, 0 packets received, 100% packet loss",
+            }}),
+            ("ping", _) => json!({"result": {"status": "idle"}}),
+            _ => json!({"success": false, "error_code": -1}),
+        },
+    );
+
+    let mut api = MercusysHTTP::new(url);
+    api.login("admin", "secret").unwrap();
+    // Only probe targets were checked since, the session has expired.
+    api.stok = "expired".into();
+
+    let watchdog = Watchdog::new(WatchArgs::default(), false);
+    assert!(watchdog.is_isp_outage(&mut api, "1.1.1.1"));
+}
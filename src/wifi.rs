@@ -0,0 +1,124 @@
+use clap::builder::BoolishValueParser;
+use clap::{Args, Subcommand};
+
+use crate::mercusys::api_wireless::{WirelessNetwork, WirelessSettings};
+use crate::mercusys::http::MercusysHTTP;
+
+#[derive(Args, Debug, Clone)]
+pub struct WifiArgs {
+    #[command(subcommand)]
+    pub action: WifiAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum WifiAction {
+    /// Show the main wireless network settings.
+    Show {
+        /// print the passphrase as well.
+        #[arg(long, default_value_t = false)]
+        show_password: bool,
+    },
+
+    /// Update the main wireless network. SSID, passphrase and security apply to both bands.
+    Set(WifiSetArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct WifiSetArgs {
+    /// new network name.
+    #[arg(long)]
+    pub ssid: Option<String>,
+
+    /// new passphrase.
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// security mode.
+    #[arg(long, value_parser = ["none", "psk", "psk_sae", "sae"])]
+    pub security: Option<String>,
+
+    /// enable or disable the 2.4 GHz band (on/off).
+    #[arg(long = "band-2g", value_parser = BoolishValueParser::new())]
+    pub band_2g: Option<bool>,
+
+    /// enable or disable the 5 GHz band (on/off).
+    #[arg(long = "band-5g", value_parser = BoolishValueParser::new())]
+    pub band_5g: Option<bool>,
+
+    /// hide the SSID (on/off).
+    #[arg(long, value_parser = BoolishValueParser::new())]
+    pub hidden: Option<bool>,
+}
+
+impl WifiSetArgs {
+    fn apply_common(&self, network: &mut WirelessNetwork) {
+        if let Some(ssid) = &self.ssid {
+            network.set_ssid(ssid);
+        }
+        if let Some(password) = &self.password {
+            network.set_password(password);
+        }
+        if let Some(security) = &self.security {
+            network.encryption = security.clone();
+        }
+        if let Some(hidden) = self.hidden {
+            network.hide_ssid = hidden;
+        }
+    }
+
+    pub fn apply(&self, settings: &mut WirelessSettings) {
+        self.apply_common(&mut settings.band2_4.host);
+        self.apply_common(&mut settings.band5_1.host);
+
+        if let Some(enable) = self.band_2g {
+            settings.band2_4.host.enable = enable;
+        }
+        if let Some(enable) = self.band_5g {
+            settings.band5_1.host.enable = enable;
+        }
+    }
+}
+
+fn print_settings(settings: &WirelessSettings, show_password: bool) {
+    for (band, network) in settings.bands() {
+        let password = if show_password {
+            network.password()
+        } else {
+            "********".into()
+        };
+
+        println!("{} GHz:", band);
+        println!("  enabled:  {}", network.enable);
+        println!("  ssid:     {}", network.ssid());
+        println!("  hidden:   {}", network.hide_ssid);
+        println!("  security: {}", network.encryption);
+        println!("  password: {}", password);
+    }
+}
+
+pub fn run_wifi(api: &mut MercusysHTTP, args: &WifiArgs, dry_run: bool) -> Result<(), String> {
+    let mut settings = api
+        .get_wireless_settings()
+        .map_err(|err| format!("failed to read wireless settings: {}", err))?
+        .result;
+
+    match &args.action {
+        WifiAction::Show { show_password } => print_settings(&settings, *show_password),
+        WifiAction::Set(set_args) => {
+            set_args.apply(&mut settings);
+            settings.validate()?;
+
+            if dry_run {
+                eprintln!("dry run mode, would write:");
+                print_settings(&settings, false);
+                return Ok(());
+            }
+
+            api.set_wireless_settings(&settings)
+                .map_err(|err| format!("failed to update wireless settings: {}", err))?;
+            eprintln!("wireless settings updated, clients may need to reconnect.");
+        }
+    }
+
+    Ok(())
+}